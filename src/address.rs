@@ -2,11 +2,12 @@ use core::fmt::{Debug, Display};
 
 mod impls;
 use cheap_clone::CheapClone;
-#[cfg(feature = "resolver")]
-pub(crate) use impls::Kind;
 
-#[cfg(feature = "std")]
-pub use impls::{Domain, HostAddr, ParseDomainError, ParseHostAddrError};
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use impls::{
+  Domain, DomainParser, Host, HostAddr, MaybePortHostAddr, ParseDomainError, ParseHostAddrError,
+  ParseHostAddrErrorKind,
+};
 
 /// Address abstraction for distributed systems
 pub trait Address:
@@ -1,10 +1,14 @@
-use std::{
-  net::{IpAddr, SocketAddr},
+use core::{
+  net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
   str::FromStr,
 };
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use std::string::{String, ToString};
+
+use smol_str03::SmolStr;
 
 mod domain;
-pub use domain::{Domain, ParseDomainError};
+pub use domain::{Domain, DomainParser, ParseDomainError};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -18,9 +22,149 @@ pub use domain::{Domain, ParseDomainError};
 )]
 pub(crate) enum Kind {
   Ip(IpAddr),
+  /// An IPv6 address with a zone id, e.g. `fe80::1%eth0`.
+  Ipv6Zoned(Ipv6Addr, SmolStr),
   Domain(Domain),
 }
 
+impl Kind {
+  #[inline]
+  const fn rank(&self) -> u8 {
+    match self {
+      Self::Ip(_) => 0,
+      Self::Ipv6Zoned(_, _) => 1,
+      Self::Domain(_) => 2,
+    }
+  }
+
+  /// Returns the socket address if it can be resolved without a lookup, otherwise
+  /// the domain to look up.
+  ///
+  /// A domain which is actually an IP literal is never produced by the parser, but can
+  /// come from data decoded without validation, so it is not sent to DNS either.
+  #[cfg(feature = "resolver")]
+  pub(crate) fn literal_socket_addr(&self, port: u16) -> Result<SocketAddr, &Domain> {
+    match self {
+      Self::Ip(ip) => Ok(SocketAddr::new(*ip, port)),
+      Self::Ipv6Zoned(ip, zone) => Ok(Self::zoned_socket_addr(*ip, zone, port)),
+      Self::Domain(name) => name
+        .as_str()
+        .parse()
+        .map(|ip| SocketAddr::new(ip, port))
+        .map_err(|_| name),
+    }
+  }
+
+  /// Returns the domain, if this is a domain.
+  #[cfg(feature = "resolver")]
+  #[inline]
+  pub(crate) const fn domain(&self) -> Option<&Domain> {
+    match self {
+      Self::Domain(name) => Some(name),
+      _ => None,
+    }
+  }
+
+  /// Returns the socket address of an IPv6 address with zone id.
+  ///
+  /// Numeric zone ids are used as the scope id, other zone ids (e.g. interface names)
+  /// cannot be mapped without the help of the OS, so the scope id is left as `0`.
+  #[inline]
+  pub(crate) fn zoned_socket_addr(ip: Ipv6Addr, zone: &str, port: u16) -> SocketAddr {
+    SocketAddr::V6(core::net::SocketAddrV6::new(
+      ip,
+      port,
+      0,
+      zone.parse().unwrap_or(0),
+    ))
+  }
+}
+
+/// Mirrors the unstable [`IpAddr::is_global`].
+const fn is_global_ip(ip: &IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(ip) => is_global_v4(ip),
+    IpAddr::V6(ip) => is_global_v6(ip),
+  }
+}
+
+const fn is_global_v4(ip: &Ipv4Addr) -> bool {
+  let [a, b, c, d] = ip.octets();
+  // "this network", 0.0.0.0/8
+  !(a == 0
+    || ip.is_private()
+    // shared, 100.64.0.0/10
+    || (a == 100 && (b & 0b1100_0000) == 0b0100_0000)
+    || ip.is_loopback()
+    || ip.is_link_local()
+    // 192.0.0.0/24, except for 192.0.0.9 and 192.0.0.10 which are globally routable
+    || (a == 192 && b == 0 && c == 0 && d != 9 && d != 10)
+    || ip.is_documentation()
+    // benchmarking, 198.18.0.0/15
+    || (a == 198 && (b & 0xfe) == 18)
+    // reserved, 240.0.0.0/4, including broadcast
+    || (a & 0xf0) == 240)
+}
+
+const fn is_global_v6(ip: &Ipv6Addr) -> bool {
+  let s = ip.segments();
+  !(ip.is_unspecified()
+    || ip.is_loopback()
+    // IPv4-mapped, ::ffff:0:0/96
+    || (s[0] == 0 && s[1] == 0 && s[2] == 0 && s[3] == 0 && s[4] == 0 && s[5] == 0xffff)
+    // IPv4-IPv6 translation, 64:ff9b:1::/48
+    || (s[0] == 0x64 && s[1] == 0xff9b && s[2] == 1)
+    // discard-only, 100::/64
+    || (s[0] == 0x100 && s[1] == 0 && s[2] == 0 && s[3] == 0)
+    // IETF protocol assignments, 2001::/23, except for some globally routable ones
+    || (s[0] == 0x2001 && s[1] < 0x200
+      && !(
+        // port control protocol anycast, 2001:1::1
+        u128::from_be_bytes(ip.octets()) == 0x2001_0001_0000_0000_0000_0000_0000_0001
+        // traversal using relays around NAT anycast, 2001:1::2
+        || u128::from_be_bytes(ip.octets()) == 0x2001_0001_0000_0000_0000_0000_0000_0002
+        // AMT, 2001:3::/32
+        || s[1] == 3
+        // AS112-v6, 2001:4:112::/48
+        || (s[1] == 4 && s[2] == 0x112)
+        // ORCHIDv2, 2001:20::/28
+        || (s[1] >= 0x20 && s[1] <= 0x2f)
+      ))
+    // documentation, 2001:db8::/32 and 3fff::/20
+    || (s[0] == 0x2001 && s[1] == 0xdb8)
+    || (s[0] == 0x3fff && s[1] <= 0x0fff)
+    // unique local, fc00::/7
+    || (s[0] & 0xfe00) == 0xfc00
+    // unicast link local, fe80::/10
+    || (s[0] & 0xffc0) == 0xfe80)
+}
+
+/// Parses a bracketed IPv6 address with zone id, e.g. `[fe80::1%eth0]`.
+fn parse_zoned(host: &str) -> Option<(Ipv6Addr, &str)> {
+  let (ip, zone) = host.strip_prefix('[')?.strip_suffix(']')?.split_once('%')?;
+  if zone.is_empty() {
+    return None;
+  }
+  ip.parse().ok().map(|ip| (ip, zone))
+}
+
+/// Parses an IP host without port, e.g. `127.0.0.1`, `::1`, `[::1]` or `[fe80::1%eth0]`.
+fn parse_ip_host(host: &str) -> Option<Kind> {
+  if let Ok(ip) = host.parse::<IpAddr>() {
+    return Some(Kind::Ip(ip));
+  }
+
+  if let Some(ip) = host
+    .strip_prefix('[')
+    .and_then(|s| s.strip_suffix(']'))
+    .and_then(|s| s.parse::<Ipv6Addr>().ok())
+  {
+    return Some(Kind::Ip(IpAddr::V6(ip)));
+  }
+
+  parse_zoned(host).map(|(ip, zone)| Kind::Ipv6Zoned(ip, SmolStr::new(zone)))
+}
+
 impl PartialOrd for Kind {
   fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
     Some(self.cmp(other))
@@ -31,16 +175,158 @@ impl Ord for Kind {
   fn cmp(&self, other: &Self) -> core::cmp::Ordering {
     match (self, other) {
       (Self::Ip(a), Self::Ip(b)) => a.cmp(b),
+      (Self::Ipv6Zoned(a, az), Self::Ipv6Zoned(b, bz)) => a.cmp(b).then_with(|| az.cmp(bz)),
       (Self::Domain(a), Self::Domain(b)) => a.cmp(b),
-      (Self::Ip(_), Self::Domain(_)) => core::cmp::Ordering::Less,
-      (Self::Domain(_), Self::Ip(_)) => core::cmp::Ordering::Greater,
+      (a, b) => a.rank().cmp(&b.rank()),
+    }
+  }
+}
+
+/// The host of a [`HostAddr`], i.e. the address without port.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Host {
+  /// An IP address.
+  Ip(IpAddr),
+  /// A domain name.
+  Domain(Domain),
+}
+
+impl core::fmt::Display for Host {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Ip(ip) => ip.fmt(f),
+      Self::Domain(name) => name.fmt(f),
+    }
+  }
+}
+
+impl From<IpAddr> for Host {
+  fn from(ip: IpAddr) -> Self {
+    Self::Ip(ip)
+  }
+}
+
+impl From<Ipv4Addr> for Host {
+  fn from(ip: Ipv4Addr) -> Self {
+    Self::Ip(IpAddr::V4(ip))
+  }
+}
+
+impl From<Ipv6Addr> for Host {
+  fn from(ip: Ipv6Addr) -> Self {
+    Self::Ip(IpAddr::V6(ip))
+  }
+}
+
+impl From<Domain> for Host {
+  fn from(name: Domain) -> Self {
+    Self::Domain(name)
+  }
+}
+
+impl From<Host> for Kind {
+  fn from(host: Host) -> Self {
+    match host {
+      Host::Ip(ip) => Self::Ip(ip),
+      Host::Domain(name) => Self::Domain(name),
     }
   }
 }
 
-/// An error which can be returned when parsing a [`HostAddr`].
+/// An error which can be returned when parsing a [`HostAddr`], with the input
+/// which failed to parse.
+///
+/// # Example
+///
+/// ```
+/// use nodecraft::{HostAddr, ParseHostAddrErrorKind};
+///
+/// let err = HostAddr::try_from(":80").unwrap_err();
+/// assert_eq!(err.input(), ":80");
+/// assert!(matches!(err.kind(), ParseHostAddrErrorKind::Domain(_)));
+/// assert!(err.to_string().contains("\":80\""));
+/// ```
+#[derive(Debug)]
+pub struct ParseHostAddrError {
+  input: SmolStr,
+  kind: ParseHostAddrErrorKind,
+}
+
+impl ParseHostAddrError {
+  #[inline]
+  fn new(input: &str, kind: ParseHostAddrErrorKind) -> Self {
+    Self {
+      input: SmolStr::new(input),
+      kind,
+    }
+  }
+
+  /// Returns the input which failed to parse.
+  #[inline]
+  pub fn input(&self) -> &str {
+    self.input.as_str()
+  }
+
+  /// Returns why the input failed to parse.
+  #[inline]
+  pub const fn kind(&self) -> &ParseHostAddrErrorKind {
+    &self.kind
+  }
+
+  /// Consumes the error and returns why the input failed to parse.
+  #[inline]
+  pub fn into_kind(self) -> ParseHostAddrErrorKind {
+    self.kind
+  }
+}
+
+impl core::fmt::Display for ParseHostAddrError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    if self.input.is_empty() {
+      write!(f, "invalid address: {}", self.kind)
+    } else {
+      write!(f, "invalid address {:?}: {}", self.input, self.kind)
+    }
+  }
+}
+
+impl core::error::Error for ParseHostAddrError {}
+
+/// Creates an error without the input, e.g. for `?` conversions.
+impl From<ParseHostAddrErrorKind> for ParseHostAddrError {
+  #[inline]
+  fn from(kind: ParseHostAddrErrorKind) -> Self {
+    Self {
+      input: SmolStr::default(),
+      kind,
+    }
+  }
+}
+
+impl From<ParseDomainError> for ParseHostAddrError {
+  #[inline]
+  fn from(e: ParseDomainError) -> Self {
+    ParseHostAddrErrorKind::from(e).into()
+  }
+}
+
+impl From<core::num::ParseIntError> for ParseHostAddrError {
+  #[inline]
+  fn from(e: core::num::ParseIntError) -> Self {
+    ParseHostAddrErrorKind::from(e).into()
+  }
+}
+
+impl From<core::str::Utf8Error> for ParseHostAddrError {
+  #[inline]
+  fn from(e: core::str::Utf8Error) -> Self {
+    ParseHostAddrErrorKind::from(e).into()
+  }
+}
+
+/// The reason why parsing a [`HostAddr`] failed, see [`ParseHostAddrError::kind`].
 #[derive(Debug, thiserror::Error)]
-pub enum ParseHostAddrError {
+pub enum ParseHostAddrErrorKind {
   /// Returned if the provided str is missing port.
   #[error("address is missing port")]
   PortNotFound,
@@ -50,6 +336,18 @@ pub enum ParseHostAddrError {
   /// Returned if the provided str is not a valid port.
   #[error("invalid port: {0}")]
   Port(#[from] core::num::ParseIntError),
+  /// Returned if the provided port is a number, but larger than [`u16::MAX`].
+  #[error("port {value} is out of range, the maximum is {}", u16::MAX)]
+  PortOutOfRange {
+    /// The port which was provided.
+    value: u64,
+  },
+  /// Returned if the provided bytes are not valid UTF-8.
+  #[error("address is not valid utf8: {0}")]
+  Utf8(#[from] core::str::Utf8Error),
+  /// Returned if the scheme of the address is not valid.
+  #[error("invalid scheme: {0}")]
+  Scheme(SmolStr),
 }
 
 /// A host address which supports both `domain:port` and socket address.
@@ -58,6 +356,22 @@ pub enum ParseHostAddrError {
 /// 1. `www.example.com:8080`
 /// 2. `[::1]:8080`
 /// 3. `127.0.0.1:8080`
+/// 4. `[fe80::1%eth0]:8080`
+///
+/// Any of the above can be prefixed by a URL-like scheme, e.g. `grpc://www.example.com:8080`,
+/// which is kept and available through [`HostAddr::scheme`]. Addresses are schemeless by default.
+///
+/// ## Ordering
+///
+/// Addresses are ordered by host first, then by port. Hosts are ordered as follows,
+/// and this order is stable across releases, so it can be relied on for sorting:
+///
+/// 1. IP addresses, with all IPv4 addresses before all IPv6 addresses, and then by
+///    the bytes of the address (the same as [`IpAddr`]).
+/// 2. IPv6 addresses with zone id, by address and then by zone id.
+/// 3. Domains, by their [`as_str`](Domain::as_str) representation.
+///
+/// Addresses which only differ by scheme are ordered by scheme, with schemeless addresses first.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 #[cfg_attr(
   feature = "rkyv",
@@ -70,6 +384,7 @@ pub enum ParseHostAddrError {
 pub struct HostAddr {
   pub(crate) kind: Kind,
   pub(crate) port: u16,
+  pub(crate) scheme: Option<SmolStr>,
 }
 
 impl PartialOrd for HostAddr {
@@ -80,56 +395,222 @@ impl PartialOrd for HostAddr {
 
 impl Ord for HostAddr {
   fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-    match self.kind.cmp(&other.kind) {
-      core::cmp::Ordering::Equal => self.port.cmp(&other.port),
-      ord => ord,
-    }
+    self
+      .kind
+      .cmp(&other.kind)
+      .then_with(|| self.port.cmp(&other.port))
+      .then_with(|| self.scheme.cmp(&other.scheme))
   }
 }
 
 impl core::fmt::Display for HostAddr {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    if let Some(scheme) = &self.scheme {
+      write!(f, "{scheme}://")?;
+    }
+
     match &self.kind {
       Kind::Ip(addr) => write!(f, "{}", SocketAddr::new(*addr, self.port)),
+      Kind::Ipv6Zoned(addr, zone) => write!(f, "[{}%{}]:{}", addr, zone, self.port),
       Kind::Domain(name) => write!(f, "{}:{}", name.as_str(), self.port),
     }
   }
 }
 
+/// Human-readable formats use the `Display` form of the address, e.g. `"grpc://127.0.0.1:80"`.
+///
+/// Binary formats use a compact `(tag, bytes, port, scheme)` tuple, where `tag` is one of
+/// the `COMPACT_*` constants below and `bytes` are the octets of an IP (followed by the zone
+/// id for a zoned IPv6 address), or the ASCII form of a domain.
 #[cfg(feature = "serde")]
-impl serde::Serialize for HostAddr {
-  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-  where
-    S: serde::Serializer,
-  {
-    match &self.kind {
-      Kind::Ip(ip) => SocketAddr::new(*ip, self.port)
-        .to_string()
-        .serialize(serializer),
-      Kind::Domain(name) => {
-        let s = format!("{}:{}", name.as_str(), self.port);
-        s.serialize(serializer)
+const _: () = {
+  use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+  };
+
+  const COMPACT_IPV4: u8 = 0;
+  const COMPACT_IPV6: u8 = 1;
+  const COMPACT_IPV6_ZONED: u8 = 2;
+  const COMPACT_DOMAIN: u8 = 3;
+
+  struct Bytes<'a>(&'a [u8]);
+
+  impl Serialize for Bytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.serialize_bytes(self.0)
+    }
+  }
+
+  struct ByteBuf(std::vec::Vec<u8>);
+
+  impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      struct ByteBufVisitor;
+
+      impl<'de> Visitor<'de> for ByteBufVisitor {
+        type Value = ByteBuf;
+
+        fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+          f.write_str("bytes")
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+          Ok(ByteBuf(v.into()))
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: std::vec::Vec<u8>) -> Result<Self::Value, E> {
+          Ok(ByteBuf(v))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+          let mut buf = std::vec::Vec::with_capacity(seq.size_hint().unwrap_or(0).min(256));
+          while let Some(b) = seq.next_element()? {
+            buf.push(b);
+          }
+          Ok(ByteBuf(buf))
+        }
       }
+
+      deserializer.deserialize_bytes(ByteBufVisitor)
     }
   }
-}
 
-#[cfg(feature = "serde")]
-impl<'de> serde::Deserialize<'de> for HostAddr {
-  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-  where
-    D: serde::Deserializer<'de>,
-  {
-    <&str as serde::Deserialize>::deserialize(deserializer)
-      .and_then(|s| Self::from_str(s).map_err(<D::Error as serde::de::Error>::custom))
+  impl Serialize for HostAddr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      if serializer.is_human_readable() {
+        // the same as `Display`, without allocating
+        return serializer.collect_str(self);
+      }
+
+      let mut octets = [0u8; 16];
+      let (tag, bytes, zone) = match &self.kind {
+        Kind::Ip(IpAddr::V4(ip)) => {
+          octets[..4].copy_from_slice(&ip.octets());
+          (COMPACT_IPV4, &octets[..4], "")
+        }
+        Kind::Ip(IpAddr::V6(ip)) => {
+          octets.copy_from_slice(&ip.octets());
+          (COMPACT_IPV6, &octets[..], "")
+        }
+        Kind::Ipv6Zoned(ip, zone) => {
+          octets.copy_from_slice(&ip.octets());
+          (COMPACT_IPV6_ZONED, &octets[..], zone.as_str())
+        }
+        Kind::Domain(name) => (COMPACT_DOMAIN, name.as_str().as_bytes(), ""),
+      };
+
+      let mut tuple = serializer.serialize_tuple(4)?;
+      tuple.serialize_element(&tag)?;
+      if zone.is_empty() {
+        tuple.serialize_element(&Bytes(bytes))?;
+      } else {
+        let mut buf = std::vec::Vec::with_capacity(bytes.len() + zone.len());
+        buf.extend_from_slice(bytes);
+        buf.extend_from_slice(zone.as_bytes());
+        tuple.serialize_element(&Bytes(&buf))?;
+      }
+      tuple.serialize_element(&self.port)?;
+      tuple.serialize_element(&self.scheme())?;
+      tuple.end()
+    }
   }
-}
+
+  struct CompactVisitor;
+
+  impl<'de> Visitor<'de> for CompactVisitor {
+    type Value = HostAddr;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+      f.write_str("a (tag, bytes, port, scheme) tuple")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+      let tag: u8 = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+      let ByteBuf(bytes) = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+      let port: u16 = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+      let scheme: Option<SmolStr> = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+
+      let invalid = || de::Error::invalid_value(de::Unexpected::Bytes(&bytes), &self);
+      let kind = match tag {
+        COMPACT_IPV4 => <[u8; 4]>::try_from(bytes.as_slice())
+          .map(|octets| Kind::Ip(IpAddr::from(octets)))
+          .map_err(|_| invalid())?,
+        COMPACT_IPV6 => <[u8; 16]>::try_from(bytes.as_slice())
+          .map(|octets| Kind::Ip(IpAddr::from(octets)))
+          .map_err(|_| invalid())?,
+        COMPACT_IPV6_ZONED if bytes.len() > 16 => {
+          let (octets, zone) = bytes.split_at(16);
+          let octets = <[u8; 16]>::try_from(octets).map_err(|_| invalid())?;
+          let zone = core::str::from_utf8(zone).map_err(|_| invalid())?;
+          Kind::Ipv6Zoned(Ipv6Addr::from(octets), SmolStr::new(zone))
+        }
+        COMPACT_DOMAIN => {
+          Kind::Domain(Domain::try_from(bytes.as_slice()).map_err(de::Error::custom)?)
+        }
+        COMPACT_IPV6_ZONED => return Err(invalid()),
+        tag => {
+          return Err(de::Error::invalid_value(
+            de::Unexpected::Unsigned(tag as u64),
+            &"a tag from 0 to 3",
+          ))
+        }
+      };
+      let scheme = scheme
+        .map(|scheme| parse_scheme(&scheme))
+        .transpose()
+        .map_err(de::Error::custom)?;
+
+      Ok(HostAddr { kind, port, scheme })
+    }
+  }
+
+  impl<'de> Deserialize<'de> for HostAddr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      if deserializer.is_human_readable() {
+        <&str as Deserialize>::deserialize(deserializer)
+          .and_then(|s| Self::from_str(s).map_err(<D::Error as de::Error>::custom))
+      } else {
+        deserializer.deserialize_tuple(4, CompactVisitor)
+      }
+    }
+  }
+};
 
 impl From<SocketAddr> for HostAddr {
   fn from(addr: SocketAddr) -> Self {
-    Self {
-      kind: Kind::Ip(addr.ip()),
-      port: addr.port(),
+    match addr {
+      SocketAddr::V6(addr) if addr.scope_id() != 0 => Self {
+        kind: Kind::Ipv6Zoned(*addr.ip(), SmolStr::new(addr.scope_id().to_string())),
+        port: addr.port(),
+        scheme: None,
+      },
+      addr => Self {
+        kind: Kind::Ip(addr.ip()),
+        port: addr.port(),
+        scheme: None,
+      },
     }
   }
 }
@@ -139,15 +620,29 @@ impl From<(IpAddr, u16)> for HostAddr {
     Self {
       kind: Kind::Ip(addr.0),
       port: addr.1,
+      scheme: None,
     }
   }
 }
 
+impl From<(Ipv4Addr, u16)> for HostAddr {
+  fn from(addr: (Ipv4Addr, u16)) -> Self {
+    (IpAddr::V4(addr.0), addr.1).into()
+  }
+}
+
+impl From<(Ipv6Addr, u16)> for HostAddr {
+  fn from(addr: (Ipv6Addr, u16)) -> Self {
+    (IpAddr::V6(addr.0), addr.1).into()
+  }
+}
+
 impl From<(Domain, u16)> for HostAddr {
   fn from(addr: (Domain, u16)) -> Self {
     Self {
       kind: Kind::Domain(addr.0),
       port: addr.1,
+      scheme: None,
     }
   }
 }
@@ -168,28 +663,86 @@ impl TryFrom<&str> for HostAddr {
   }
 }
 
+impl TryFrom<&[u8]> for HostAddr {
+  type Error = ParseHostAddrError;
+
+  fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+    Self::try_from_bytes(value)
+  }
+}
+
 impl FromStr for HostAddr {
   type Err = ParseHostAddrError;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s).map_err(|kind| ParseHostAddrError::new(s, kind))
+  }
+}
+
+/// Validates a URL scheme, which is `ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`
+/// according to [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-3.1).
+fn parse_scheme(scheme: &str) -> Result<SmolStr, ParseHostAddrErrorKind> {
+  let mut chars = scheme.chars();
+  let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+    && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+  if valid {
+    Ok(SmolStr::new(scheme))
+  } else {
+    Err(ParseHostAddrErrorKind::Scheme(SmolStr::new(scheme)))
+  }
+}
+
+fn parse_port(port: &str) -> Result<u16, ParseHostAddrErrorKind> {
+  port.parse().map_err(|e| match port.parse::<u64>() {
+    Ok(value) => ParseHostAddrErrorKind::PortOutOfRange { value },
+    Err(_) => ParseHostAddrErrorKind::Port(e),
+  })
+}
+
+impl HostAddr {
+  /// Parses an address which may be prefixed by a scheme.
+  fn parse(s: &str) -> Result<Self, ParseHostAddrErrorKind> {
+    match s.split_once("://") {
+      Some((scheme, rest)) => {
+        let scheme = parse_scheme(scheme)?;
+        Self::parse_authority(rest).map(|addr| Self {
+          scheme: Some(scheme),
+          ..addr
+        })
+      }
+      None => Self::parse_authority(s),
+    }
+  }
+
+  /// Parses an address without scheme.
+  fn parse_authority(s: &str) -> Result<Self, ParseHostAddrErrorKind> {
     let res: Result<SocketAddr, _> = s.parse();
     match res {
       Ok(addr) => Ok(addr.into()),
       Err(_) => {
         let res: Result<IpAddr, _> = s.parse();
         match res {
-          Ok(_) => Err(ParseHostAddrError::PortNotFound),
+          Ok(_) => Err(ParseHostAddrErrorKind::PortNotFound),
           Err(_) => {
             let Some((domain, port)) = s.rsplit_once(':') else {
-              return Err(ParseHostAddrError::PortNotFound);
+              return Err(ParseHostAddrErrorKind::PortNotFound);
             };
 
-            let port = port.parse()?;
+            let port = parse_port(port)?;
+            if let Some((ip, zone)) = parse_zoned(domain) {
+              return Ok(Self {
+                kind: Kind::Ipv6Zoned(ip, SmolStr::new(zone)),
+                port,
+                scheme: None,
+              });
+            }
+
             let dns = Domain::try_from(domain)?;
 
             Ok(Self {
               kind: Kind::Domain(dns),
               port,
+              scheme: None,
             })
           }
         }
@@ -207,13 +760,9 @@ impl TryFrom<(&str, u16)> for HostAddr {
       Ok(addr) => Ok(Self {
         kind: Kind::Ip(addr),
         port,
+        scheme: None,
       }),
-      Err(_) => Domain::try_from(domain)
-        .map(|dns| Self {
-          kind: Kind::Domain(dns),
-          port,
-        })
-        .map_err(Into::into),
+      Err(_) => Self::from_domain(domain, port),
     }
   }
 }
@@ -225,14 +774,87 @@ impl HostAddr {
       .map(|d| Self {
         kind: Kind::Domain(d),
         port,
+        scheme: None,
       })
-      .map_err(Into::into)
+      .map_err(|e| ParseHostAddrError::new(s, e.into()))
+  }
+
+  /// Parses an address from bytes, which avoids an extra UTF-8 check compared to
+  /// calling [`core::str::from_utf8`] before [`HostAddr::from_str`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::HostAddr;
+  ///
+  /// let addr = HostAddr::try_from_bytes(b"www.example.com:8080").unwrap();
+  /// assert_eq!(addr.domain(), Some("www.example.com"));
+  /// assert_eq!(addr.port(), 8080);
+  /// ```
+  pub fn try_from_bytes(src: &[u8]) -> Result<Self, ParseHostAddrError> {
+    match core::str::from_utf8(src) {
+      Ok(s) => Self::from_str(s),
+      Err(e) => Err(ParseHostAddrError::new(
+        &String::from_utf8_lossy(src),
+        e.into(),
+      )),
+    }
+  }
+
+  /// Parses an address, using `default` as the port if `s` does not contain one.
+  ///
+  /// Plain IPs, bracketed IPv6 addresses (e.g. `[::1]`) and domains are accepted
+  /// without a port. An explicit port must still be valid.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::HostAddr;
+  ///
+  /// let addr = HostAddr::from_str_with_default_port("www.example.com", 443).unwrap();
+  /// assert_eq!(addr.port(), 443);
+  ///
+  /// let addr = HostAddr::from_str_with_default_port("[::1]", 80).unwrap();
+  /// assert_eq!(addr.to_string(), "[::1]:80");
+  ///
+  /// let addr = HostAddr::from_str_with_default_port("www.example.com:8080", 443).unwrap();
+  /// assert_eq!(addr.port(), 8080);
+  /// ```
+  pub fn from_str_with_default_port(s: &str, default: u16) -> Result<Self, ParseHostAddrError> {
+    MaybePortHostAddr::from_str(s).map(|addr| addr.with_default_port(default))
+  }
+
+  /// Parses an address which may be prefixed by a URL-like `scheme://`, returning
+  /// the scheme (if any) together with the address.
+  ///
+  /// The address is parsed the same way as [`HostAddr::from_str`], so the scheme is
+  /// validated and also kept in the returned address, and the port is still required.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::HostAddr;
+  ///
+  /// let (scheme, addr) = HostAddr::parse_with_scheme("https://www.example.com:8443").unwrap();
+  /// assert_eq!(scheme, Some("https"));
+  /// assert_eq!(addr.scheme(), Some("https"));
+  /// assert_eq!(addr.domain(), Some("www.example.com"));
+  /// assert_eq!(addr.port(), 8443);
+  ///
+  /// let (scheme, addr) = HostAddr::parse_with_scheme("www.example.com:80").unwrap();
+  /// assert_eq!(scheme, None);
+  /// assert_eq!(addr.port(), 80);
+  /// ```
+  pub fn parse_with_scheme(s: &str) -> Result<(Option<&str>, Self), ParseHostAddrError> {
+    let addr = Self::parse(s).map_err(|kind| ParseHostAddrError::new(s, kind))?;
+    let scheme = addr.scheme.as_ref().map(|scheme| &s[..scheme.len()]);
+    Ok((scheme, addr))
   }
 
   /// Returns the domain of the address if this address can only be represented by domain name
   pub fn domain(&self) -> Option<&str> {
     match &self.kind {
-      Kind::Ip(_) => None,
+      Kind::Ip(_) | Kind::Ipv6Zoned(_, _) => None,
       Kind::Domain(name) => Some(name.as_str()),
     }
   }
@@ -240,7 +862,7 @@ impl HostAddr {
   /// Returns the fqdn of the address if this address can only be represented by domain name
   pub fn fqdn(&self) -> Option<&str> {
     match &self.kind {
-      Kind::Ip(_) => None,
+      Kind::Ip(_) | Kind::Ipv6Zoned(_, _) => None,
       Kind::Domain(name) => Some(name.fqdn_str()),
     }
   }
@@ -249,10 +871,101 @@ impl HostAddr {
   pub const fn ip(&self) -> Option<IpAddr> {
     match &self.kind {
       Kind::Ip(addr) => Some(*addr),
+      Kind::Ipv6Zoned(addr, _) => Some(IpAddr::V6(*addr)),
       Kind::Domain(_) => None,
     }
   }
 
+  /// Returns the socket address if this address can be represented by [`SocketAddr`],
+  /// i.e. it is not a domain.
+  ///
+  /// The scope id of an IPv6 address with zone id is only set for numeric zone ids.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::HostAddr;
+  ///
+  /// let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+  /// assert_eq!(addr.socket_addr(), Some("127.0.0.1:8080".parse().unwrap()));
+  ///
+  /// let addr = HostAddr::try_from("www.example.com:8080").unwrap();
+  /// assert_eq!(addr.socket_addr(), None);
+  /// ```
+  #[inline]
+  pub fn socket_addr(&self) -> Option<SocketAddr> {
+    match &self.kind {
+      Kind::Ip(ip) => Some(SocketAddr::new(*ip, self.port)),
+      Kind::Ipv6Zoned(ip, zone) => Some(Kind::zoned_socket_addr(*ip, zone, self.port)),
+      Kind::Domain(_) => None,
+    }
+  }
+
+  /// Returns `true` if this is a loopback address.
+  ///
+  /// Always returns `false` for a domain, as it can't be determined without resolving.
+  #[inline]
+  pub fn is_loopback(&self) -> bool {
+    self.ip().is_some_and(|ip| ip.is_loopback())
+  }
+
+  /// Returns `true` if this is an unspecified address, e.g. `0.0.0.0` or `::`.
+  ///
+  /// Always returns `false` for a domain.
+  #[inline]
+  pub fn is_unspecified(&self) -> bool {
+    self.ip().is_some_and(|ip| ip.is_unspecified())
+  }
+
+  /// Returns `Some(true)` if the address appears to be globally reachable, following the
+  /// same rules as the (unstable) [`IpAddr::is_global`].
+  ///
+  /// Returns `None` for a domain, as it can't be determined without resolving.
+  #[inline]
+  pub fn is_global(&self) -> Option<bool> {
+    self.ip().map(|ip| is_global_ip(&ip))
+  }
+
+  /// Returns the zone id if this address is an IPv6 address with zone id, e.g. `[fe80::1%eth0]:8080`
+  pub fn zone(&self) -> Option<&str> {
+    match &self.kind {
+      Kind::Ipv6Zoned(_, zone) => Some(zone.as_str()),
+      _ => None,
+    }
+  }
+
+  /// Returns the scheme of the address, e.g. `grpc` for `grpc://www.example.com:80`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::HostAddr;
+  ///
+  /// let addr: HostAddr = "grpc://www.example.com:80".parse().unwrap();
+  /// assert_eq!(addr.scheme(), Some("grpc"));
+  /// assert_eq!(addr.to_string(), "grpc://www.example.com:80");
+  /// ```
+  #[inline]
+  pub fn scheme(&self) -> Option<&str> {
+    self.scheme.as_deref()
+  }
+
+  /// Set the scheme, or remove it if `scheme` is `None`.
+  #[inline]
+  pub fn set_scheme(&mut self, scheme: Option<&str>) -> Result<&mut Self, ParseHostAddrError> {
+    self.scheme = scheme
+      .map(|scheme| parse_scheme(scheme).map_err(|kind| ParseHostAddrError::new(scheme, kind)))
+      .transpose()?;
+    Ok(self)
+  }
+
+  /// Set the scheme in builder pattern, or remove it if `scheme` is `None`.
+  #[inline]
+  pub fn with_scheme(mut self, scheme: Option<&str>) -> Result<Self, ParseHostAddrError> {
+    self.set_scheme(scheme)?;
+    Ok(self)
+  }
+
   /// Returns the port
   #[inline]
   pub const fn port(&self) -> u16 {
@@ -272,14 +985,322 @@ impl HostAddr {
     self.port = port;
     self
   }
+
+  /// Set the host, keeping the port (and the scheme, if any).
+  #[inline]
+  pub fn set_host(&mut self, host: impl Into<Host>) -> &mut Self {
+    self.kind = host.into().into();
+    self
+  }
+
+  /// Set the host in builder pattern, keeping the port (and the scheme, if any).
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use std::net::Ipv4Addr;
+  ///
+  /// use nodecraft::HostAddr;
+  ///
+  /// let addr = HostAddr::try_from("www.example.com:8080").unwrap();
+  /// let addr = addr.with_host(Ipv4Addr::LOCALHOST);
+  /// assert_eq!(addr.to_string(), "127.0.0.1:8080");
+  /// ```
+  #[inline]
+  pub fn with_host(mut self, host: impl Into<Host>) -> Self {
+    self.set_host(host);
+    self
+  }
+
+  /// Set the domain in builder pattern, keeping the port (and the scheme, if any).
+  #[inline]
+  pub fn with_domain(self, domain: Domain) -> Self {
+    self.with_host(domain)
+  }
+}
+
+/// A [`HostAddr`] whose port may be absent, e.g. parsed from user input where the
+/// port is filled in later with [`with_default_port`](MaybePortHostAddr::with_default_port).
+///
+/// It accepts everything [`HostAddr`] does, plus IPs, bracketed IPv6 addresses
+/// (e.g. `[::1]`) and domains without port.
+///
+/// # Example
+///
+/// ```
+/// use nodecraft::MaybePortHostAddr;
+///
+/// let addr: MaybePortHostAddr = "www.example.com".parse().unwrap();
+/// assert_eq!(addr.port(), None);
+/// assert_eq!(addr.port_or(443), 443);
+/// assert_eq!(addr.with_default_port(443).to_string(), "www.example.com:443");
+///
+/// let addr: MaybePortHostAddr = "www.example.com:8443".parse().unwrap();
+/// assert_eq!(addr.port_or(443), 8443);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaybePortHostAddr {
+  kind: Kind,
+  port: Option<u16>,
+  scheme: Option<SmolStr>,
+}
+
+impl MaybePortHostAddr {
+  /// Returns the port, or `None` if it is absent.
+  #[inline]
+  pub const fn port(&self) -> Option<u16> {
+    self.port
+  }
+
+  /// Returns the port, or `default` if it is absent.
+  #[inline]
+  pub const fn port_or(&self, default: u16) -> u16 {
+    match self.port {
+      Some(port) => port,
+      None => default,
+    }
+  }
+
+  /// Returns the domain of the address if this address can only be represented by domain name
+  #[inline]
+  pub fn domain(&self) -> Option<&str> {
+    match &self.kind {
+      Kind::Ip(_) | Kind::Ipv6Zoned(_, _) => None,
+      Kind::Domain(name) => Some(name.as_str()),
+    }
+  }
+
+  /// Returns the ip of the address if this address can be represented by [`IpAddr`]
+  #[inline]
+  pub const fn ip(&self) -> Option<IpAddr> {
+    match &self.kind {
+      Kind::Ip(addr) => Some(*addr),
+      Kind::Ipv6Zoned(addr, _) => Some(IpAddr::V6(*addr)),
+      Kind::Domain(_) => None,
+    }
+  }
+
+  /// Returns the scheme of the address, e.g. `grpc` for `grpc://www.example.com`.
+  #[inline]
+  pub fn scheme(&self) -> Option<&str> {
+    self.scheme.as_deref()
+  }
+
+  /// Converts into a [`HostAddr`], using `default` as the port if it is absent.
+  #[inline]
+  pub fn with_default_port(self, default: u16) -> HostAddr {
+    HostAddr {
+      kind: self.kind,
+      port: self.port.unwrap_or(default),
+      scheme: self.scheme,
+    }
+  }
+
+  fn parse(s: &str) -> Result<Self, ParseHostAddrErrorKind> {
+    let (scheme, rest) = match s.split_once("://") {
+      Some((scheme, rest)) => (Some(parse_scheme(scheme)?), rest),
+      None => (None, s),
+    };
+
+    let (kind, port) = if let Some(kind) = parse_ip_host(rest) {
+      (kind, None)
+    } else if rest.contains(':') {
+      let addr = HostAddr::parse_authority(rest)?;
+      (addr.kind, Some(addr.port))
+    } else {
+      (Kind::Domain(Domain::try_from(rest)?), None)
+    };
+
+    Ok(Self { kind, port, scheme })
+  }
+}
+
+impl From<HostAddr> for MaybePortHostAddr {
+  fn from(addr: HostAddr) -> Self {
+    Self {
+      kind: addr.kind,
+      port: Some(addr.port),
+      scheme: addr.scheme,
+    }
+  }
+}
+
+impl FromStr for MaybePortHostAddr {
+  type Err = ParseHostAddrError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s).map_err(|kind| ParseHostAddrError::new(s, kind))
+  }
+}
+
+impl TryFrom<&str> for MaybePortHostAddr {
+  type Error = ParseHostAddrError;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    Self::from_str(value)
+  }
+}
+
+impl core::fmt::Display for MaybePortHostAddr {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    if let Some(scheme) = &self.scheme {
+      write!(f, "{scheme}://")?;
+    }
+
+    match (&self.kind, self.port) {
+      (Kind::Ip(addr), Some(port)) => write!(f, "{}", SocketAddr::new(*addr, port)),
+      (Kind::Ip(IpAddr::V4(addr)), None) => write!(f, "{addr}"),
+      (Kind::Ip(IpAddr::V6(addr)), None) => write!(f, "[{addr}]"),
+      (Kind::Ipv6Zoned(addr, zone), port) => {
+        write!(f, "[{addr}%{zone}]")?;
+        match port {
+          Some(port) => write!(f, ":{port}"),
+          None => Ok(()),
+        }
+      }
+      (Kind::Domain(name), Some(port)) => write!(f, "{}:{}", name.as_str(), port),
+      (Kind::Domain(name), None) => f.write_str(name.as_str()),
+    }
+  }
+}
+
+#[cfg(feature = "resolver")]
+impl HostAddr {
+  /// Resolves the address with the given resolver.
+  ///
+  /// This is a shorthand for [`AddressResolver::resolve`](crate::resolver::AddressResolver::resolve).
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use std::collections::HashMap;
+  ///
+  /// use nodecraft::{resolver::{r#static::StaticResolver, AddressResolver}, Domain, HostAddr};
+  ///
+  /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+  /// let hosts = HashMap::from([(
+  ///   Domain::try_from("db.internal").unwrap(),
+  ///   vec!["10.0.0.1".parse().unwrap()],
+  /// )]);
+  /// let resolver = StaticResolver::<agnostic::tokio::TokioRuntime>::new(hosts).await.unwrap();
+  /// let addr = HostAddr::try_from("db.internal:5432").unwrap();
+  /// assert_eq!(addr.resolve_with(&resolver).await.unwrap().to_string(), "10.0.0.1:5432");
+  /// # });
+  /// ```
+  #[inline]
+  pub fn resolve_with<'a, R>(
+    &'a self,
+    resolver: &'a R,
+  ) -> impl core::future::Future<Output = Result<R::ResolvedAddress, R::Error>> + Send + 'a
+  where
+    R: crate::resolver::AddressResolver<Address = Self>,
+  {
+    resolver.resolve(self)
+  }
 }
 
 impl cheap_clone::CheapClone for HostAddr {}
 
+/// The characters used to generate arbitrary domain labels, zones and schemes,
+/// which always start with a letter.
+#[cfg(any(feature = "arbitrary", feature = "quickcheck"))]
+const ARBITRARY_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+#[cfg(any(feature = "arbitrary", feature = "quickcheck"))]
+const ARBITRARY_LETTERS: &[u8] = ARBITRARY_CHARS.split_at(26).0;
+
+#[cfg(any(feature = "arbitrary", feature = "quickcheck"))]
+impl HostAddr {
+  /// Builds an arbitrary address, `kind` picks the variant.
+  fn from_arbitrary_parts(
+    kind: u8,
+    ip: IpAddr,
+    zoned: Ipv6Addr,
+    mut word: impl FnMut() -> String,
+    port: u16,
+    scheme: bool,
+  ) -> Self {
+    let kind = match kind % 3 {
+      0 => Kind::Ip(ip),
+      1 => Kind::Ipv6Zoned(zoned, SmolStr::new(word())),
+      _ => {
+        let domain = [word(), word(), word()].join(".");
+        Kind::Domain(Domain::try_from(domain).expect("generated domain must be valid"))
+      }
+    };
+    let scheme = scheme.then(|| SmolStr::new(word()));
+    Self { kind, port, scheme }
+  }
+}
+
+#[cfg(feature = "arbitrary")]
+const _: () = {
+  use arbitrary::{Arbitrary, Unstructured};
+
+  fn word(u: &mut Unstructured<'_>) -> String {
+    let len = u.int_in_range(1..=10).unwrap_or(1);
+    (0..len)
+      .map(|i| {
+        let chars = if i == 0 {
+          ARBITRARY_LETTERS
+        } else {
+          ARBITRARY_CHARS
+        };
+        *u.choose(chars).unwrap_or(&b'a') as char
+      })
+      .collect()
+  }
+
+  impl<'a> Arbitrary<'a> for HostAddr {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+      let kind = u8::arbitrary(u)?;
+      let ip = IpAddr::arbitrary(u)?;
+      let zoned = Ipv6Addr::arbitrary(u)?;
+      let port = u16::arbitrary(u)?;
+      let scheme = bool::arbitrary(u)?;
+      Ok(Self::from_arbitrary_parts(
+        kind,
+        ip,
+        zoned,
+        || word(u),
+        port,
+        scheme,
+      ))
+    }
+  }
+};
+
+#[cfg(feature = "quickcheck")]
+const _: () = {
+  use quickcheck::{Arbitrary, Gen};
+
+  fn word(g: &mut Gen) -> String {
+    let len = usize::arbitrary(g) % 10 + 1;
+    (0..len)
+      .map(|i| {
+        let chars = if i == 0 {
+          ARBITRARY_LETTERS
+        } else {
+          ARBITRARY_CHARS
+        };
+        *g.choose(chars).unwrap() as char
+      })
+      .collect()
+  }
+
+  impl Arbitrary for HostAddr {
+    fn arbitrary(g: &mut Gen) -> Self {
+      let kind = u8::arbitrary(g);
+      let ip = IpAddr::arbitrary(g);
+      let zoned = Ipv6Addr::arbitrary(g);
+      let port = u16::arbitrary(g);
+      let scheme = bool::arbitrary(g);
+      Self::from_arbitrary_parts(kind, ip, zoned, || word(g), port, scheme)
+    }
+  }
+};
+
 #[cfg(test)]
 mod tests {
-  use core::net::{Ipv4Addr, Ipv6Addr};
-
   use super::*;
   use rand::{distr::Alphanumeric, rng, Rng, RngCore};
 
@@ -294,6 +1315,7 @@ mod tests {
       Self {
         kind: Kind::Ip(IpAddr::V4(Ipv4Addr::from(addr))),
         port,
+        scheme: None,
       }
     }
 
@@ -307,6 +1329,7 @@ mod tests {
       Self {
         kind: Kind::Ip(IpAddr::V6(Ipv6Addr::from(addr))),
         port,
+        scheme: None,
       }
     }
 
@@ -326,6 +1349,7 @@ mod tests {
       Self {
         kind: Kind::Domain(Domain::try_from(domain).unwrap()),
         port,
+        scheme: None,
       }
     }
   }
@@ -396,6 +1420,30 @@ mod tests {
     assert_eq!(v4.partial_cmp(&domain), Some(core::cmp::Ordering::Less));
   }
 
+  #[test]
+  fn test_ord_contract() {
+    let sorted = [
+      "10.0.0.1:80",
+      "10.0.0.1:8080",
+      "192.168.0.1:80",
+      "255.255.255.255:1",
+      "[::]:1",
+      "[::1]:80",
+      "[fe80::1]:80",
+      "[fe80::1%eth0]:80",
+      "[fe80::1%eth1]:80",
+      "a.example.com:80",
+      "a.example.com:8080",
+      "b.example.com:1",
+    ]
+    .map(|s| HostAddr::from_str(s).unwrap());
+
+    let mut addrs = sorted.clone();
+    addrs.reverse();
+    addrs.sort();
+    assert_eq!(addrs, sorted);
+  }
+
   #[cfg(feature = "serde")]
   #[test]
   fn test_serde() {
@@ -429,12 +1477,552 @@ mod tests {
     assert_eq!(a.fqdn().unwrap(), "www.example.com.");
   }
 
+  #[test]
+  fn test_try_from_bytes() {
+    let a = HostAddr::try_from_bytes(b"127.0.0.1:8080").unwrap();
+    assert_eq!(a.ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    assert_eq!(a.port(), 8080);
+
+    let a = HostAddr::try_from(b"[::1]:8080".as_slice()).unwrap();
+    assert_eq!(a.ip(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    assert_eq!(a.port(), 8080);
+
+    let a = HostAddr::try_from_bytes("测试.com:80".as_bytes()).unwrap();
+    assert_eq!(a.domain(), Some("xn--0zwm56d.com"));
+    assert_eq!(a.port(), 80);
+
+    let err = HostAddr::try_from_bytes(b"www.\xffexample.com:80").unwrap_err();
+    assert!(matches!(err.kind(), ParseHostAddrErrorKind::Utf8(_)));
+    assert!(err.to_string().contains("utf8"));
+
+    assert!(matches!(
+      HostAddr::try_from_bytes(b"www.example.com"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::PortNotFound)
+    ));
+  }
+
+  #[test]
+  fn test_from_str_with_default_port() {
+    let a = HostAddr::from_str_with_default_port("www.example.com", 80).unwrap();
+    assert_eq!(a.domain(), Some("www.example.com"));
+    assert_eq!(a.port(), 80);
+
+    let a = HostAddr::from_str_with_default_port("www.example.com:8080", 80).unwrap();
+    assert_eq!(a.domain(), Some("www.example.com"));
+    assert_eq!(a.port(), 8080);
+
+    let a = HostAddr::from_str_with_default_port("127.0.0.1", 80).unwrap();
+    assert_eq!(
+      a,
+      HostAddr::from("127.0.0.1:80".parse::<SocketAddr>().unwrap())
+    );
+
+    let a = HostAddr::from_str_with_default_port("127.0.0.1:8080", 80).unwrap();
+    assert_eq!(a.ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    assert_eq!(a.port(), 8080);
+
+    let a = HostAddr::from_str_with_default_port("[::1]", 80).unwrap();
+    assert_eq!(a.ip(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    assert_eq!(a.port(), 80);
+
+    let a = HostAddr::from_str_with_default_port("[::1]:8080", 80).unwrap();
+    assert_eq!(a.ip(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    assert_eq!(a.port(), 8080);
+
+    assert!(matches!(
+      HostAddr::from_str_with_default_port("www.example.com:abc", 80),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Port(_))
+    ));
+  }
+
+  #[test]
+  fn test_port_out_of_range() {
+    for s in ["example.com:99999", "127.0.0.1:99999", "[::1]:99999"] {
+      let err = HostAddr::from_str(s).unwrap_err();
+      assert!(matches!(
+        err.kind(),
+        ParseHostAddrErrorKind::PortOutOfRange { value: 99999 }
+      ));
+      assert_eq!(
+        err.to_string(),
+        format!("invalid address {s:?}: port 99999 is out of range, the maximum is 65535")
+      );
+    }
+
+    assert_eq!(HostAddr::from_str("example.com:0").unwrap().port(), 0);
+    assert_eq!(
+      HostAddr::from_str("example.com:65535").unwrap().port(),
+      65535
+    );
+
+    assert!(matches!(
+      HostAddr::from_str("example.com:-1"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Port(_))
+    ));
+    assert!(matches!(
+      HostAddr::from_str_with_default_port("example.com:99999", 80),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::PortOutOfRange { value: 99999 })
+    ));
+  }
+
+  #[test]
+  fn test_default_port_bare_ipv6() {
+    let a = HostAddr::from_str_with_default_port("::1", 80).unwrap();
+    assert_eq!(a, HostAddr::from("[::1]:80".parse::<SocketAddr>().unwrap()));
+
+    let a = HostAddr::from_str_with_default_port("fe80::1:2", 80).unwrap();
+    assert_eq!(a.ip(), Some("fe80::1:2".parse().unwrap()));
+    assert_eq!(a.port(), 80);
+
+    let a = HostAddr::from_str_with_default_port("[fe80::1:2]", 80).unwrap();
+    assert_eq!(a.ip(), Some("fe80::1:2".parse().unwrap()));
+    assert_eq!(a.port(), 80);
+
+    let a = HostAddr::from_str_with_default_port("10.0.0.1", 80).unwrap();
+    assert_eq!(
+      a,
+      HostAddr::from("10.0.0.1:80".parse::<SocketAddr>().unwrap())
+    );
+  }
+
+  #[test]
+  fn test_ip_properties() {
+    let a = HostAddr::from_str("127.0.0.1:8080").unwrap();
+    assert!(a.is_loopback());
+    assert!(!a.is_unspecified());
+    assert_eq!(a.is_global(), Some(false));
+
+    let a = HostAddr::from_str("[::1]:8080").unwrap();
+    assert!(a.is_loopback());
+    assert!(!a.is_unspecified());
+    assert_eq!(a.is_global(), Some(false));
+
+    let a = HostAddr::from_str("0.0.0.0:8080").unwrap();
+    assert!(!a.is_loopback());
+    assert!(a.is_unspecified());
+    assert_eq!(a.is_global(), Some(false));
+
+    let a = HostAddr::from_str("[::]:8080").unwrap();
+    assert!(a.is_unspecified());
+
+    let a = HostAddr::from_str("localhost:8080").unwrap();
+    assert!(!a.is_loopback());
+    assert!(!a.is_unspecified());
+    assert_eq!(a.is_global(), None);
+
+    for (addr, global) in [
+      ("8.8.8.8:53", true),
+      ("1.1.1.1:53", true),
+      ("192.0.0.9:53", true),
+      ("10.0.0.1:53", false),
+      ("100.64.0.1:53", false),
+      ("169.254.0.1:53", false),
+      ("192.0.2.1:53", false),
+      ("198.18.0.1:53", false),
+      ("255.255.255.255:53", false),
+      ("[2606:4700:4700::1111]:53", true),
+      ("[2001:1::1]:53", true),
+      ("[2001:db8::1]:53", false),
+      ("[fd00::1]:53", false),
+      ("[fe80::1]:53", false),
+      ("[::ffff:8.8.8.8]:53", false),
+    ] {
+      let a = HostAddr::from_str(addr).unwrap();
+      assert_eq!(a.is_global(), Some(global), "{addr}");
+    }
+  }
+
+  #[test]
+  fn test_zone() {
+    let a = HostAddr::from_str("[fe80::1%eth0]:8080").unwrap();
+    assert_eq!(a.ip(), Some("fe80::1".parse().unwrap()));
+    assert_eq!(a.zone(), Some("eth0"));
+    assert_eq!(a.port(), 8080);
+    assert_eq!(a.domain(), None);
+    assert_eq!(a.to_string(), "[fe80::1%eth0]:8080");
+    assert_eq!(HostAddr::from_str(&a.to_string()).unwrap(), a);
+
+    let a = HostAddr::from_str("[fe80::1%3]:8080").unwrap();
+    assert_eq!(a.zone(), Some("3"));
+    assert_eq!(a.to_string(), "[fe80::1%3]:8080");
+
+    let a = HostAddr::from_str_with_default_port("[fe80::1%eth0]", 80).unwrap();
+    assert_eq!(a.to_string(), "[fe80::1%eth0]:80");
+
+    let a = HostAddr::from_str("[fe80::1]:8080").unwrap();
+    assert_eq!(a.zone(), None);
+    assert_eq!(a.to_string(), "[fe80::1]:8080");
+
+    assert!(HostAddr::from_str("[fe80::1%]:8080").is_err());
+    assert!(HostAddr::from_str("127.0.0.1%eth0:8080").is_err());
+    assert!(HostAddr::from_str("[127.0.0.1%eth0]:8080").is_err());
+  }
+
+  #[test]
+  fn test_set_host() {
+    let a = HostAddr::from_str("www.example.com:8080").unwrap();
+    let mut a = a.with_host(Ipv4Addr::LOCALHOST);
+    assert_eq!(a.ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    assert_eq!(a.domain(), None);
+    assert_eq!(a.port(), 8080);
+
+    a.set_host(Ipv6Addr::LOCALHOST);
+    assert_eq!(a.to_string(), "[::1]:8080");
+
+    let a = a.with_domain(Domain::try_from("canonical.example.com").unwrap());
+    assert_eq!(a.domain(), Some("canonical.example.com"));
+    assert_eq!(a.ip(), None);
+    assert_eq!(a.port(), 8080);
+
+    // the zone id and the scheme
+    let mut a = HostAddr::from_str("grpc://[fe80::1%eth0]:80").unwrap();
+    a.set_host(Host::Domain(Domain::try_from("example.com").unwrap()));
+    assert_eq!(a.zone(), None);
+    assert_eq!(a.to_string(), "grpc://example.com:80");
+
+    assert_eq!(Host::from(Ipv6Addr::LOCALHOST).to_string(), "::1");
+    assert_eq!(
+      Host::from(Domain::try_from("example.com.").unwrap()).to_string(),
+      "example.com"
+    );
+  }
+
+  #[test]
+  fn test_scheme() {
+    let a = HostAddr::from_str("grpc://example.com:80").unwrap();
+    assert_eq!(a.scheme(), Some("grpc"));
+    assert_eq!(a.domain(), Some("example.com"));
+    assert_eq!(a.port(), 80);
+    assert_eq!(a.to_string(), "grpc://example.com:80");
+    assert_eq!(HostAddr::from_str(&a.to_string()).unwrap(), a);
+
+    let a = HostAddr::from_str("http://[fe80::1%eth0]:8080").unwrap();
+    assert_eq!(a.scheme(), Some("http"));
+    assert_eq!(a.zone(), Some("eth0"));
+    assert_eq!(a.to_string(), "http://[fe80::1%eth0]:8080");
+
+    // schemeless by default, and the scheme is part of the identity
+    let plain = HostAddr::from_str("example.com:80").unwrap();
+    assert_eq!(plain.scheme(), None);
+    assert_ne!(plain, HostAddr::from_str("grpc://example.com:80").unwrap());
+    assert!(plain < HostAddr::from_str("grpc://example.com:80").unwrap());
+
+    let mut a = plain.with_scheme(Some("grpc+tls")).unwrap();
+    assert_eq!(a.to_string(), "grpc+tls://example.com:80");
+    a.set_scheme(None).unwrap();
+    assert_eq!(a.to_string(), "example.com:80");
+    assert!(matches!(
+      a.set_scheme(Some("1grpc")),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Scheme(_))
+    ));
+
+    assert!(matches!(
+      HostAddr::from_str("://example.com:80"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Scheme(_))
+    ));
+    assert!(matches!(
+      HostAddr::from_str("gr pc://example.com:80"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Scheme(_))
+    ));
+
+    // `parse_with_scheme` validates and keeps the scheme the same way
+    let (scheme, a) = HostAddr::parse_with_scheme("grpc://example.com:80").unwrap();
+    assert_eq!(scheme, Some("grpc"));
+    assert_eq!(a.scheme(), Some("grpc"));
+    assert_eq!(a, HostAddr::from_str("grpc://example.com:80").unwrap());
+    assert!(matches!(
+      HostAddr::parse_with_scheme("gr pc://example.com:80"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Scheme(_))
+    ));
+    assert!(matches!(
+      HostAddr::parse_with_scheme("://example.com:80"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::Scheme(_))
+    ));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_scheme_serde() {
+    let a = HostAddr::from_str("grpc://127.0.0.1:80").unwrap();
+    let s = serde_json::to_string(&a).unwrap();
+    assert_eq!(s, "\"grpc://127.0.0.1:80\"");
+    assert_eq!(serde_json::from_str::<HostAddr>(&s).unwrap(), a);
+  }
+
+  #[test]
+  fn test_parse_with_scheme() {
+    let (scheme, a) = HostAddr::parse_with_scheme("https://www.example.com:8443").unwrap();
+    assert_eq!(scheme, Some("https"));
+    assert_eq!(a.domain(), Some("www.example.com"));
+    assert_eq!(a.port(), 8443);
+
+    let (scheme, a) = HostAddr::parse_with_scheme("tcp://127.0.0.1:9000").unwrap();
+    assert_eq!(scheme, Some("tcp"));
+    assert_eq!(a.ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    assert_eq!(a.port(), 9000);
+
+    let (scheme, a) = HostAddr::parse_with_scheme("udp://[::1]:9000").unwrap();
+    assert_eq!(scheme, Some("udp"));
+    assert_eq!(a.ip(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+
+    let (scheme, a) = HostAddr::parse_with_scheme("example.com:80").unwrap();
+    assert_eq!(scheme, None);
+    assert_eq!(a.domain(), Some("example.com"));
+    assert_eq!(a.port(), 80);
+
+    assert!(matches!(
+      HostAddr::parse_with_scheme("https://www.example.com"),
+      Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::PortNotFound)
+    ));
+    // the returned address is the same as the one from `from_str`
+    let (_, a) = HostAddr::parse_with_scheme("https://www.example.com:8443").unwrap();
+    assert_eq!(
+      a,
+      HostAddr::from_str("https://www.example.com:8443").unwrap()
+    );
+  }
+
+  #[test]
+  fn test_error_input() {
+    let err = HostAddr::from_str(":80").unwrap_err();
+    assert_eq!(err.input(), ":80");
+    assert!(err.to_string().contains("\":80\""));
+
+    let err = HostAddr::from_str("www.example.com").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "invalid address \"www.example.com\": address is missing port"
+    );
+    assert!(matches!(
+      err.into_kind(),
+      ParseHostAddrErrorKind::PortNotFound
+    ));
+
+    let err = HostAddr::from_str_with_default_port("exa mple.com", 80).unwrap_err();
+    assert_eq!(err.input(), "exa mple.com");
+    let err = HostAddr::from_domain("exa mple.com", 80).unwrap_err();
+    assert_eq!(err.input(), "exa mple.com");
+    let err = HostAddr::try_from_bytes(b"www.\xffexample.com:80").unwrap_err();
+    assert_eq!(err.input(), "www.\u{fffd}example.com:80");
+    let err = HostAddr::from_str("example.com:80")
+      .unwrap()
+      .with_scheme(Some("1grpc"))
+      .unwrap_err();
+    assert_eq!(err.input(), "1grpc");
+  }
+
+  #[test]
+  fn test_error_from() {
+    fn port(s: &str) -> Result<u16, ParseHostAddrError> {
+      Ok(s.parse::<u16>()?)
+    }
+
+    fn domain(s: &str) -> Result<Domain, ParseHostAddrError> {
+      Ok(Domain::try_from(s)?)
+    }
+
+    let err = port("http").unwrap_err();
+    assert_eq!(err.input(), "");
+    assert!(matches!(err.kind(), ParseHostAddrErrorKind::Port(_)));
+    assert!(err.to_string().starts_with("invalid address: invalid port"));
+    let err = domain("exa mple.com").unwrap_err();
+    assert!(matches!(err.kind(), ParseHostAddrErrorKind::Domain(_)));
+    let err = ParseHostAddrError::from(ParseHostAddrErrorKind::PortNotFound);
+    assert!(matches!(err.kind(), ParseHostAddrErrorKind::PortNotFound));
+  }
+
   #[test]
   fn negative_test() {
     let p = HostAddr::try_from("127.0.0.1");
-    assert!(matches!(p, Err(ParseHostAddrError::PortNotFound)));
+    assert!(matches!(p, Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::PortNotFound)));
 
     let p = HostAddr::try_from("www.example.com");
-    assert!(matches!(p, Err(ParseHostAddrError::PortNotFound)));
+    assert!(matches!(p, Err(e) if matches!(e.kind(), ParseHostAddrErrorKind::PortNotFound)));
+  }
+
+  #[cfg(feature = "resolver")]
+  #[tokio::test]
+  async fn test_resolve_with() {
+    use crate::resolver::{address::HostAddrResolver, AddressResolver};
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = HostAddrResolver::<TokioRuntime>::new(Default::default());
+    let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+    assert_eq!(
+      addr.resolve_with(&resolver).await.unwrap(),
+      "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+    );
+
+    let addr = HostAddr::try_from("[::1]:8080").unwrap();
+    assert_eq!(
+      addr.resolve_with(&resolver).await.unwrap(),
+      resolver.resolve(&addr).await.unwrap()
+    );
+  }
+
+  #[test]
+  fn test_socket_addr() {
+    let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+    assert_eq!(
+      addr.socket_addr(),
+      Some("127.0.0.1:8080".parse::<SocketAddr>().unwrap())
+    );
+
+    let addr = HostAddr::try_from("[::1]:8080").unwrap();
+    assert_eq!(
+      addr.socket_addr(),
+      Some("[::1]:8080".parse::<SocketAddr>().unwrap())
+    );
+
+    let addr = HostAddr::try_from("[fe80::1%3]:8080").unwrap();
+    assert_eq!(
+      addr.socket_addr(),
+      Some("[fe80::1%3]:8080".parse::<SocketAddr>().unwrap())
+    );
+
+    let addr = HostAddr::try_from("www.example.com:8080").unwrap();
+    assert_eq!(addr.socket_addr(), None);
+  }
+
+  #[test]
+  fn test_maybe_port() {
+    let a = MaybePortHostAddr::from_str("example.com").unwrap();
+    assert_eq!(a.domain(), Some("example.com"));
+    assert_eq!(a.port(), None);
+    assert_eq!(a.port_or(80), 80);
+    assert_eq!(a.to_string(), "example.com");
+    assert_eq!(
+      a.with_default_port(80),
+      HostAddr::from_str("example.com:80").unwrap()
+    );
+
+    let a = MaybePortHostAddr::from_str("example.com:9000").unwrap();
+    assert_eq!(a.port(), Some(9000));
+    assert_eq!(a.port_or(80), 9000);
+    assert_eq!(a.to_string(), "example.com:9000");
+
+    for (s, port) in [
+      ("127.0.0.1", None),
+      ("127.0.0.1:80", Some(80)),
+      ("[::1]", None),
+      ("[::1]:80", Some(80)),
+      ("[fe80::1%eth0]", None),
+      ("[fe80::1%eth0]:80", Some(80)),
+      ("grpc://example.com", None),
+      ("grpc://example.com:80", Some(80)),
+    ] {
+      let a = MaybePortHostAddr::try_from(s).unwrap();
+      assert_eq!(a.port(), port, "{s}");
+      assert_eq!(a.to_string(), s);
+    }
+    assert_eq!(
+      MaybePortHostAddr::from_str("::1").unwrap().to_string(),
+      "[::1]"
+    );
+    assert_eq!(
+      MaybePortHostAddr::from_str("grpc://example.com")
+        .unwrap()
+        .scheme(),
+      Some("grpc")
+    );
+
+    let err = MaybePortHostAddr::from_str("example.com:abc").unwrap_err();
+    assert!(matches!(err.kind(), ParseHostAddrErrorKind::Port(_)));
+    assert_eq!(err.input(), "example.com:abc");
+    assert!(MaybePortHostAddr::from_str("exa mple.com").is_err());
+
+    let addr = HostAddr::from_str("grpc://127.0.0.1:80").unwrap();
+    assert_eq!(
+      MaybePortHostAddr::from(addr.clone()).with_default_port(0),
+      addr
+    );
+  }
+
+  #[test]
+  fn test_from_ip_port() {
+    let addr = HostAddr::from((Ipv4Addr::LOCALHOST, 8080));
+    assert_eq!(addr.ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    assert_eq!(addr.port(), 8080);
+
+    let addr = HostAddr::from((Ipv6Addr::LOCALHOST, 9090));
+    assert_eq!(addr.ip(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    assert_eq!(addr.port(), 9090);
+    assert_eq!(addr, HostAddr::try_from("[::1]:9090").unwrap());
+  }
+
+  #[cfg(feature = "quickcheck")]
+  #[quickcheck_macros::quickcheck]
+  fn fuzzy_from_str(addr: HostAddr) -> bool {
+    addr.to_string().parse::<HostAddr>().unwrap() == addr
+  }
+
+  #[cfg(all(feature = "serde", feature = "quickcheck"))]
+  #[quickcheck_macros::quickcheck]
+  fn fuzzy_serde(addr: HostAddr) -> bool {
+    let serialized = serde_json::to_string(&addr).unwrap();
+    let deserialized: HostAddr = serde_json::from_str(&serialized).unwrap();
+    addr == deserialized
+  }
+
+  #[cfg(all(feature = "serde", feature = "quickcheck"))]
+  #[quickcheck_macros::quickcheck]
+  fn fuzzy_serde_compact(addr: HostAddr) -> bool {
+    let serialized = bincode::serialize(&addr).unwrap();
+    let deserialized: HostAddr = bincode::deserialize(&serialized).unwrap();
+    addr == deserialized
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_compact() {
+    for s in [
+      "127.0.0.1:80",
+      "[::1]:8080",
+      "[fe80::1%eth0]:8080",
+      "www.example.com:443",
+      "测试.com:80",
+      "grpc://www.example.com:443",
+    ] {
+      let addr = HostAddr::from_str(s).unwrap();
+
+      let json = serde_json::to_string(&addr).unwrap();
+      assert_eq!(json, format!("\"{addr}\""));
+      assert_eq!(serde_json::from_str::<HostAddr>(&json).unwrap(), addr);
+
+      let compact = bincode::serialize(&addr).unwrap();
+      assert_eq!(bincode::deserialize::<HostAddr>(&compact).unwrap(), addr);
+    }
+
+    // tag, 4 bytes with their length, port and no scheme
+    let addr = HostAddr::from_str("127.0.0.1:80").unwrap();
+    assert_eq!(bincode::serialize(&addr).unwrap().len(), 1 + 8 + 4 + 2 + 1);
+
+    let bad = bincode::serialize(&(0u8, [1u8, 2, 3].as_slice(), 80u16, None::<&str>)).unwrap();
+    assert!(bincode::deserialize::<HostAddr>(&bad).is_err());
+    let bad = bincode::serialize(&(4u8, [1u8; 4].as_slice(), 80u16, None::<&str>)).unwrap();
+    assert!(bincode::deserialize::<HostAddr>(&bad).is_err());
+    let bad = bincode::serialize(&(3u8, b"exa mple.com".as_slice(), 80u16, None::<&str>)).unwrap();
+    assert!(bincode::deserialize::<HostAddr>(&bad).is_err());
+  }
+
+  #[cfg(feature = "arbitrary")]
+  #[test]
+  fn test_arbitrary_variants() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let (mut ip, mut zoned, mut domain, mut scheme) = (false, false, false, false);
+    for _ in 0..256 {
+      let mut data = [0u8; 128];
+      rng().fill_bytes(&mut data);
+      let addr = HostAddr::arbitrary(&mut Unstructured::new(&data)).unwrap();
+      match addr.kind {
+        Kind::Ip(_) => ip = true,
+        Kind::Ipv6Zoned(_, _) => zoned = true,
+        Kind::Domain(_) => domain = true,
+      }
+      scheme |= addr.scheme().is_some();
+      assert_eq!(addr.to_string().parse::<HostAddr>().unwrap(), addr);
+    }
+    assert!(ip && zoned && domain && scheme);
   }
 }
@@ -1,20 +1,95 @@
 use core::fmt;
 use std::borrow::Cow;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use std::{
+  format,
+  string::{String, ToString},
+  vec::Vec,
+};
 
-use idna::{domain_to_ascii_cow, AsciiDenyList};
+use idna::{
+  domain_to_ascii_cow,
+  uts46::{DnsLength, Hyphens, Uts46},
+  AsciiDenyList,
+};
 use smol_str03::SmolStr;
 
 /// A type which encapsulates a string that is a syntactically domain name.
+///
+/// ## Storage
+///
+/// A domain is always stored in its fully-qualified form, i.e. with a trailing dot,
+/// whether or not it was created with one. Both [`as_str`](Domain::as_str) (without the dot)
+/// and [`fqdn_str`](Domain::fqdn_str) (with the dot) are then borrowed from the same buffer,
+/// without allocating. The cost is a single byte, which stays inline for domains of up to
+/// 22 bytes.
+///
+/// The dot is never exposed unless asked for: [`Display`](core::fmt::Display), serde,
+/// equality, ordering and hashing all use [`as_str`](Domain::as_str), so systems which
+/// reject the trailing dot can use a `Domain` as is.
+///
+/// Human-readable serde formats (e.g. JSON) use the [Unicode form](Domain::to_unicode)
+/// instead, and binary ones the ASCII form. Both forms are accepted when deserializing.
+///
+/// Whether the source had a trailing dot is remembered separately, see
+/// [`was_fqdn`](Domain::was_fqdn).
 #[derive(Clone, Debug, Eq)]
 #[cfg_attr(
   feature = "rkyv",
   derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
 )]
-#[cfg_attr(
-  feature = "rkyv",
-  rkyv(compare(PartialEq), derive(PartialEq, Eq, PartialOrd, Ord, Hash))
-)]
-pub struct Domain(SmolStr);
+pub struct Domain(
+  SmolStr,
+  /// Whether the source ended with a dot, which is ignored by comparisons.
+  bool,
+);
+
+#[cfg(feature = "rkyv")]
+const _: () = {
+  impl ArchivedDomain {
+    #[inline]
+    fn fqdn_str(&self) -> &str {
+      self.0.as_str()
+    }
+  }
+
+  impl PartialEq<Domain> for ArchivedDomain {
+    #[inline]
+    fn eq(&self, other: &Domain) -> bool {
+      self.fqdn_str() == other.fqdn_str()
+    }
+  }
+
+  impl PartialEq for ArchivedDomain {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+      self.fqdn_str() == other.fqdn_str()
+    }
+  }
+
+  impl Eq for ArchivedDomain {}
+
+  impl PartialOrd for ArchivedDomain {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+      Some(self.cmp(other))
+    }
+  }
+
+  impl Ord for ArchivedDomain {
+    #[inline]
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+      self.fqdn_str().cmp(other.fqdn_str())
+    }
+  }
+
+  impl core::hash::Hash for ArchivedDomain {
+    #[inline]
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+      self.fqdn_str().hash(state)
+    }
+  }
+};
 
 #[cfg(feature = "serde")]
 const _: () = {
@@ -23,7 +98,42 @@ const _: () = {
     where
       S: serde::Serializer,
     {
-      self.as_str().serialize(serializer)
+      if !serializer.is_human_readable() {
+        return self.as_str().serialize(serializer);
+      }
+
+      // Only use the Unicode form if it parses back to the same domain, e.g. not for
+      // mixed-case ASCII domains, which are lowercased by the conversion.
+      let unicode = self.to_unicode();
+      let round_trips = match &unicode {
+        Cow::Borrowed(s) => *s == self.as_str(),
+        Cow::Owned(s) => {
+          Domain::try_from(s.as_str()).is_ok_and(|domain| domain.as_str() == self.as_str())
+        }
+      };
+
+      if round_trips {
+        unicode.serialize(serializer)
+      } else {
+        self.as_str().serialize(serializer)
+      }
+    }
+  }
+
+  struct DomainVisitor;
+
+  impl serde::de::Visitor<'_> for DomainVisitor {
+    type Value = Domain;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a domain name")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+      E: serde::de::Error,
+    {
+      Domain::try_from(v).map_err(E::custom)
     }
   }
 
@@ -32,8 +142,7 @@ const _: () = {
     where
       D: serde::Deserializer<'de>,
     {
-      let s = <&str>::deserialize(deserializer)?;
-      s.try_into().map_err(serde::de::Error::custom)
+      deserializer.deserialize_str(DomainVisitor)
     }
   }
 };
@@ -84,21 +193,337 @@ impl Domain {
     self.0.trim_end_matches('.')
   }
 
+  /// Returns `true` if `bytes` is a valid ASCII domain, with the same rules as
+  /// [`Domain::try_from`] for ASCII inputs.
+  ///
+  /// This is a `const fn`, so domain literals can be checked at compile time.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// const _: () = assert!(Domain::is_valid_ascii(b"example.com"));
+  ///
+  /// assert!(!Domain::is_valid_ascii(b".example.com"));
+  /// assert!(!Domain::is_valid_ascii(b"example.123"));
+  /// ```
+  ///
+  /// An invalid literal fails the build:
+  ///
+  /// ```compile_fail
+  /// use nodecraft::Domain;
+  ///
+  /// const _: () = assert!(Domain::is_valid_ascii(b"exa mple.com"));
+  /// ```
+  #[inline]
+  pub const fn is_valid_ascii(bytes: &[u8]) -> bool {
+    validate(bytes).is_ok()
+  }
+
+  /// Creates a domain from an ASCII domain which has already been validated, skipping
+  /// the validation and the IDNA processing done by [`Domain::try_from`]. Only the
+  /// trailing dot is normalized.
+  ///
+  /// The caller is responsible for passing a valid, lowercase ASCII domain, e.g. one
+  /// obtained from [`Domain::as_str`]. An invalid input does not cause any memory
+  /// unsafety, but yields a `Domain` whose behavior is unspecified.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::from_ascii_unchecked("www.example.com");
+  /// assert_eq!(domain, Domain::try_from("www.example.com").unwrap());
+  /// assert_eq!(domain.fqdn_str(), "www.example.com.");
+  /// ```
+  pub fn from_ascii_unchecked(s: &str) -> Self {
+    if s.ends_with('.') {
+      return Self(SmolStr::new(s), true);
+    }
+
+    let mut domain = String::with_capacity(s.len() + 1);
+    domain.push_str(s);
+    domain.push('.');
+    Self(domain.into(), false)
+  }
+
   /// Returns the fully-qualified domain name representation.
   #[inline]
   pub fn fqdn_str(&self) -> &str {
     self.0.as_str()
   }
 
+  /// Returns `true` if the source of the domain ended with a dot, i.e. it was written
+  /// in its fully-qualified form.
+  ///
+  /// This only records the input, [`as_str`](Domain::as_str), [`fqdn_str`](Domain::fqdn_str),
+  /// equality and hashing are the same either way. It is kept by `rkyv`, but not by serde,
+  /// which serializes [`as_str`](Domain::as_str).
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let relative = Domain::try_from("example.com").unwrap();
+  /// let absolute = Domain::try_from("example.com.").unwrap();
+  /// assert!(!relative.was_fqdn());
+  /// assert!(absolute.was_fqdn());
+  /// assert_eq!(relative, absolute);
+  /// ```
+  #[inline]
+  pub const fn was_fqdn(&self) -> bool {
+    self.1
+  }
+
+  /// Returns a hash of the domain which is stable across processes, platforms and releases,
+  /// e.g. for consistent hashing.
+  ///
+  /// The hash is the 64-bit FNV-1a of [`as_str`](Domain::as_str), so it is the same
+  /// whether or not the domain was created with a trailing dot.
+  #[inline]
+  pub fn stable_hash(&self) -> u64 {
+    crate::fnv1a64(self.as_str().as_bytes())
+  }
+
+  /// Returns the human-readable Unicode form of the domain, which reverses the IDNA
+  /// processing done when the domain was created.
+  ///
+  /// Domains without any Punycode (`xn--`) label are returned as borrowed and unchanged,
+  /// without allocating, even if they contain uppercase letters. Otherwise the domain is
+  /// converted, which also lowercases it. ASCII input is only syntax-checked when creating
+  /// a domain, so a label which is not valid Punycode (e.g. `xn--a`) cannot be converted,
+  /// and the ASCII form is returned as is.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("测试.com").unwrap();
+  /// assert_eq!(domain.as_str(), "xn--0zwm56d.com");
+  /// assert_eq!(domain.to_unicode(), "测试.com");
+  /// ```
+  pub fn to_unicode(&self) -> Cow<'_, str> {
+    let punycode = self.as_str().split('.').any(|label| {
+      label
+        .get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("xn--"))
+    });
+    if !punycode {
+      return Cow::Borrowed(self.as_str());
+    }
+
+    let (domain, result) = Uts46::new().to_unicode(
+      self.as_str().as_bytes(),
+      AsciiDenyList::EMPTY,
+      Hyphens::Allow,
+    );
+    match result {
+      Ok(()) => domain,
+      Err(_) => Cow::Borrowed(self.as_str()),
+    }
+  }
+
+  /// Returns an iterator over the labels of the domain, from the leftmost label to the TLD.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("www.example.com").unwrap();
+  /// assert_eq!(domain.labels().collect::<Vec<_>>(), ["www", "example", "com"]);
+  /// ```
+  #[inline]
+  pub fn labels(&self) -> impl Iterator<Item = &str> {
+    self.as_str().split('.').filter(|label| !label.is_empty())
+  }
+
+  /// Returns an iterator over the labels of the domain, from the TLD to the leftmost label.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("www.example.com").unwrap();
+  /// assert_eq!(domain.labels_rev().collect::<Vec<_>>(), ["com", "example", "www"]);
+  /// ```
+  #[inline]
+  pub fn labels_rev(&self) -> impl Iterator<Item = &str> {
+    self.as_str().rsplit('.').filter(|label| !label.is_empty())
+  }
+
+  /// Returns the domain for display, keeping at most the `max_labels` rightmost labels
+  /// and replacing the rest with a leading `…`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("a.b.www.example.com").unwrap();
+  /// assert_eq!(domain.truncate_display(2), "…example.com");
+  /// assert_eq!(domain.truncate_display(5), "a.b.www.example.com");
+  /// ```
+  pub fn truncate_display(&self, max_labels: usize) -> String {
+    let total = self.labels().count();
+    if total <= max_labels {
+      return self.as_str().to_string();
+    }
+
+    let kept = self.labels().skip(total - max_labels);
+    let mut out = String::from("…");
+    for (i, label) in kept.enumerate() {
+      if i > 0 {
+        out.push('.');
+      }
+      out.push_str(label);
+    }
+    out
+  }
+
+  /// Creates a domain from labels ordered from the TLD to the leftmost label, which is
+  /// the reverse of [`labels`](Domain::labels).
+  ///
+  /// Each label must be non-empty and must not contain a dot, and the assembled domain is
+  /// validated the same way as [`Domain::try_from`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::from_reversed_labels(["com", "example", "www"]).unwrap();
+  /// assert_eq!(domain.as_str(), "www.example.com");
+  /// ```
+  pub fn from_reversed_labels<I, S>(labels: I) -> Result<Self, ParseDomainError>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let labels = labels.into_iter().collect::<Vec<_>>();
+    let mut domain = String::new();
+    for label in labels.iter().rev() {
+      let label = label.as_ref();
+      if label.is_empty() || label.contains('.') {
+        return Err(ParseDomainError);
+      }
+
+      if !domain.is_empty() {
+        domain.push('.');
+      }
+      domain.push_str(label);
+    }
+
+    Self::try_from_inner(domain.as_bytes())
+  }
+
+  /// Returns a new domain with `label` prepended as the leftmost label, e.g. `www`
+  /// prepended to `example.com` is `www.example.com`.
+  ///
+  /// The label must be a single non-empty label, and the new domain is validated the same
+  /// way as [`Domain::try_from`]. The new domain keeps [`was_fqdn`](Domain::was_fqdn) of `self`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("example.com").unwrap();
+  /// assert_eq!(domain.prepend_label("www").unwrap().as_str(), "www.example.com");
+  /// assert!(domain.prepend_label("a.b").is_err());
+  /// ```
+  pub fn prepend_label(&self, label: &str) -> Result<Self, ParseDomainError> {
+    if label.is_empty() || label.contains('.') {
+      return Err(ParseDomainError);
+    }
+
+    let joined = Self::try_from_inner(format!("{label}.{}", self.as_str()).as_bytes())?;
+    // IDNA maps full-width dots to dots, which would sneak extra labels in
+    if joined.labels().count() != self.labels().count() + 1 {
+      return Err(ParseDomainError);
+    }
+    Ok(Self(joined.0, self.1))
+  }
+
+  /// Returns a new domain with the labels of `self` followed by the labels of `other`,
+  /// e.g. `shard-1.eu-west` joined with `internal` is `shard-1.eu-west.internal`.
+  ///
+  /// The new domain is validated the same way as [`Domain::try_from`], which fails if it
+  /// is too long, and keeps [`was_fqdn`](Domain::was_fqdn) of `other`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let shard = Domain::try_from("shard-1.eu-west").unwrap();
+  /// let internal = Domain::try_from("internal").unwrap();
+  /// assert_eq!(shard.join(&internal).unwrap().as_str(), "shard-1.eu-west.internal");
+  /// ```
+  pub fn join(&self, other: &Domain) -> Result<Self, ParseDomainError> {
+    Self::try_from_inner(format!("{}.{}", self.as_str(), other.as_str()).as_bytes())
+      .map(|joined| Self(joined.0, other.1))
+  }
+
+  /// Returns the public suffix of the domain, according to the [Public Suffix List].
+  ///
+  /// Both ICANN suffixes (e.g. `co.uk`) and private suffixes (e.g. `github.io`) are honored.
+  ///
+  /// [Public Suffix List]: https://publicsuffix.org/
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("foo.example.co.uk").unwrap();
+  /// assert_eq!(domain.public_suffix(), Some("co.uk"));
+  /// ```
+  #[cfg(feature = "psl")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "psl")))]
+  pub fn public_suffix(&self) -> Option<&str> {
+    psl::suffix_str(self.as_str())
+  }
+
+  /// Returns the registrable domain, which is the public suffix plus one more label,
+  /// according to the [Public Suffix List].
+  ///
+  /// Returns `None` if the domain is itself a public suffix.
+  ///
+  /// [Public Suffix List]: https://publicsuffix.org/
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let domain = Domain::try_from("foo.example.co.uk").unwrap();
+  /// assert_eq!(domain.registrable_domain().unwrap().as_str(), "example.co.uk");
+  ///
+  /// let domain = Domain::try_from("co.uk").unwrap();
+  /// assert!(domain.registrable_domain().is_none());
+  /// ```
+  #[cfg(feature = "psl")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "psl")))]
+  pub fn registrable_domain(&self) -> Option<Domain> {
+    psl::domain_str(self.as_str()).and_then(|domain| Self::try_from(domain).ok())
+  }
+
   /// Create a new Domain from a string, performing IDNA processing and validation.
   pub fn try_from_inner(domain: &[u8]) -> Result<Self, ParseDomainError> {
+    let was_fqdn = domain.ends_with(b".");
     let domain = if domain.is_ascii() {
       validate(domain)?;
 
       let domain = core::str::from_utf8(domain).expect("bytes must be valid utf8");
       // Early return if already has trailing dot
       if domain.ends_with('.') {
-        return Ok(Self(domain.into()));
+        return Ok(Self(domain.into(), was_fqdn));
       }
 
       Cow::Borrowed(domain)
@@ -116,6 +541,7 @@ impl Domain {
           core::str::from_utf8(domain)
             .expect("bytes must be valid utf8")
             .into(),
+          was_fqdn,
         ));
       }
 
@@ -131,15 +557,66 @@ impl Domain {
       Ok(Self(
         // SAFETY: We know the input is valid UTF-8 from validation
         unsafe { core::str::from_utf8_unchecked(&buf[..=len]) }.into(),
+        was_fqdn,
       ))
     } else {
       // Consider pre-allocating with capacity
       let mut string = String::with_capacity(domain.len() + 1);
       string.push_str(&domain);
       string.push('.');
-      Ok(Self(string.into()))
+      Ok(Self(string.into(), was_fqdn))
     }
   }
+
+  /// Create a new Domain from a string, like [`Domain::try_from`], and also report
+  /// whether the input was converted by the IDNA processing, i.e. it was a Unicode
+  /// domain which is stored in its punycode form.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// let (domain, converted) = Domain::try_from_reporting("测试.com").unwrap();
+  /// assert_eq!(domain.as_str(), "xn--0zwm56d.com");
+  /// assert!(converted);
+  ///
+  /// let (_, converted) = Domain::try_from_reporting("www.example.com").unwrap();
+  /// assert!(!converted);
+  /// ```
+  pub fn try_from_reporting(domain: &str) -> Result<(Self, bool), ParseDomainError> {
+    // ASCII domains are only validated, the IDNA processing only runs on Unicode ones.
+    Self::try_from_inner(domain.as_bytes()).map(|d| (d, !domain.is_ascii()))
+  }
+
+  /// Create a new Domain from a string, like [`Domain::try_from`], but with the strict
+  /// UTS #46 rules, which reject more homograph-prone inputs.
+  ///
+  /// In addition to the checks done by [`Domain::try_from`] (including the bidi rules),
+  /// this applies the STD3 ASCII rules, so only letters, digits and hyphens are allowed
+  /// (no underscores), rejects hyphens at the start or the end of a label and in the
+  /// third and fourth positions of a label, and verifies the DNS length limits.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Domain;
+  ///
+  /// assert_eq!(Domain::try_from_strict("测试.com").unwrap().as_str(), "xn--0zwm56d.com");
+  /// assert!(Domain::try_from_strict("exam\u{202E}ple.com").is_err());
+  /// assert!(Domain::try_from_strict("_srv.example.com").is_err());
+  /// ```
+  pub fn try_from_strict(domain: &str) -> Result<Self, ParseDomainError> {
+    let ascii = Uts46::new()
+      .to_ascii(
+        domain.as_bytes(),
+        AsciiDenyList::STD3,
+        Hyphens::Check,
+        DnsLength::VerifyAllowRootDot,
+      )
+      .map_err(|_| ParseDomainError)?;
+    Self::try_from_inner(ascii.as_bytes())
+  }
 }
 
 #[cfg(feature = "alloc")]
@@ -190,6 +667,79 @@ impl AsRef<str> for Domain {
   }
 }
 
+/// The bytes of [`as_str`](Domain::as_str), i.e. without the trailing dot.
+impl AsRef<[u8]> for Domain {
+  fn as_ref(&self) -> &[u8] {
+    self.as_str().as_bytes()
+  }
+}
+
+/// A configurable parser of [`Domain`]s.
+///
+/// The default parser accepts the same domains as [`Domain::try_from`].
+///
+/// # Example
+///
+/// ```
+/// use nodecraft::DomainParser;
+///
+/// // RFC 1123 host names do not allow underscores
+/// let parser = DomainParser::new().with_allow_underscores(false);
+/// assert!(parser.parse("_service.example.com").is_err());
+/// assert!(parser.parse("www.example.com").is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DomainParser {
+  allow_underscores: bool,
+}
+
+impl Default for DomainParser {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DomainParser {
+  /// Creates a new [`DomainParser`] with the default options.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      allow_underscores: true,
+    }
+  }
+
+  /// Set whether underscores are allowed in labels in builder pattern.
+  #[inline]
+  pub const fn with_allow_underscores(mut self, val: bool) -> Self {
+    self.allow_underscores = val;
+    self
+  }
+
+  /// Set whether underscores are allowed in labels.
+  #[inline]
+  pub fn set_allow_underscores(&mut self, val: bool) -> &mut Self {
+    self.allow_underscores = val;
+    self
+  }
+
+  /// Returns whether underscores are allowed in labels. Default is `true`.
+  #[inline]
+  pub const fn allow_underscores(&self) -> bool {
+    self.allow_underscores
+  }
+
+  /// Parses a domain with the options of this parser.
+  pub fn parse(&self, s: &str) -> Result<Domain, ParseDomainError> {
+    let domain = Domain::try_from(s)?;
+    // checks the processed form, which is what IDNA inputs end up as
+    if !self.allow_underscores && domain.as_str().contains('_') {
+      return Err(ParseDomainError);
+    }
+    Ok(domain)
+  }
+}
+
 /// The provided input could not be parsed because
 /// it is not a syntactically-valid DNS Domain.
 #[derive(Debug)]
@@ -347,7 +897,7 @@ mod tests {
   #[test]
   fn test_basic() {
     let name = Domain::try_from(&"localhost".to_string()).unwrap();
-    assert_eq!("localhost", name.as_ref());
+    assert_eq!("localhost", AsRef::<str>::as_ref(&name));
     let err = ParseDomainError;
     println!("{}", err);
   }
@@ -363,6 +913,16 @@ mod tests {
     assert!(set.contains("localhost"));
   }
 
+  #[test]
+  fn test_as_bytes() {
+    fn bytes(val: &impl AsRef<[u8]>) -> &[u8] {
+      val.as_ref()
+    }
+
+    let name = Domain::try_from("www.example.com.").unwrap();
+    assert_eq!(bytes(&name), b"www.example.com");
+  }
+
   #[test]
   fn test_try_from_bytes() {
     use super::Domain;
@@ -399,6 +959,185 @@ mod tests {
     assert_eq!(name.to_string().as_str(), "labelendswithnumber1.bar.com");
   }
 
+  const _: () = assert!(Domain::is_valid_ascii(b"example.com"));
+  const _: () = assert!(Domain::is_valid_ascii(b"example.com."));
+  const _: () = assert!(!Domain::is_valid_ascii(b""));
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_is_valid_ascii() {
+    for (input, expected) in TESTS.iter().filter(|(input, _)| input.is_ascii()) {
+      assert_eq!(
+        Domain::is_valid_ascii(input.as_bytes()),
+        *expected,
+        "{input:?}"
+      );
+    }
+
+    assert!(!Domain::is_valid_ascii(b".example.com"));
+    assert!(!Domain::is_valid_ascii(b"example.123"));
+    assert!(!Domain::is_valid_ascii(&[b'a'; 64]));
+    assert!(!Domain::is_valid_ascii("测试.com".as_bytes()));
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_from_ascii_unchecked() {
+    let valid = TESTS
+      .iter()
+      .filter(|(input, valid)| *valid && input.is_ascii())
+      .map(|(input, _)| Domain::try_from(*input).unwrap());
+
+    for domain in valid {
+      for input in [domain.as_str(), domain.fqdn_str()] {
+        let unchecked = Domain::from_ascii_unchecked(input);
+        assert_eq!(unchecked, domain);
+        assert_eq!(unchecked.as_str(), domain.as_str());
+        assert_eq!(unchecked.fqdn_str(), domain.fqdn_str());
+      }
+    }
+  }
+
+  #[test]
+  fn test_try_from_reporting() {
+    for s in ["www.example.com", "www.example.com.", "xn--0zwm56d.com"] {
+      let (domain, converted) = Domain::try_from_reporting(s).unwrap();
+      assert_eq!(domain, Domain::try_from(s).unwrap());
+      assert!(!converted);
+    }
+
+    for s in ["测试.com", "测试.com.", "例え.テスト"] {
+      let (domain, converted) = Domain::try_from_reporting(s).unwrap();
+      assert_eq!(domain, Domain::try_from(s).unwrap());
+      assert!(domain.as_str().is_ascii());
+      assert!(converted);
+    }
+
+    assert!(Domain::try_from_reporting("exa mple.com").is_err());
+  }
+
+  #[test]
+  fn test_try_from_strict() {
+    let domain = Domain::try_from_strict("测试.com").unwrap();
+    assert_eq!(domain, Domain::try_from("测试.com").unwrap());
+    assert_eq!(
+      Domain::try_from_strict("www.example.com.").unwrap(),
+      Domain::try_from("www.example.com").unwrap()
+    );
+
+    // right-to-left override
+    assert!(Domain::try_from_strict("exam\u{202E}ple.com").is_err());
+    // left-to-right and right-to-left characters in the same label
+    assert!(Domain::try_from_strict("a\u{05D0}.com").is_err());
+    assert!(Domain::try_from("a\u{05D0}.com").is_err());
+
+    // STD3 rules and hyphen checks are only applied by the strict parser
+    assert!(Domain::try_from("_srv.example.com").is_ok());
+    assert!(Domain::try_from_strict("_srv.example.com").is_err());
+    assert!(Domain::try_from_strict("ab--c.example.com").is_err());
+  }
+
+  #[test]
+  fn test_parser() {
+    let parser = DomainParser::default();
+    assert!(parser.allow_underscores());
+    assert_eq!(
+      parser.parse("_prefixunderscorevalid.com").unwrap(),
+      Domain::try_from("_prefixunderscorevalid.com").unwrap()
+    );
+
+    let mut parser = parser.with_allow_underscores(false);
+    assert!(!parser.allow_underscores());
+    assert!(parser.parse("_prefixunderscorevalid.com").is_err());
+    assert!(parser.parse("foo_bar.com").is_err());
+    assert_eq!(parser.parse("foo.bar.com").unwrap().as_str(), "foo.bar.com");
+    assert!(parser.parse("not valid.com").is_err());
+
+    parser.set_allow_underscores(true);
+    assert!(parser.parse("foo_bar.com").is_ok());
+
+    // `Domain::try_from` is unchanged
+    assert!(Domain::try_from("_prefixunderscorevalid.com").is_ok());
+  }
+
+  #[test]
+  fn test_storage() {
+    for input in ["www.example.com", "www.example.com."] {
+      let name = Domain::try_from(input).unwrap();
+      assert_eq!(name.fqdn_str(), "www.example.com.");
+      assert_eq!(name.as_str(), "www.example.com");
+      // both views borrow the same buffer
+      assert_eq!(name.as_str().as_ptr(), name.fqdn_str().as_ptr());
+      assert_eq!(name.to_string(), "www.example.com");
+    }
+
+    let a = Domain::try_from("www.example.com").unwrap();
+    let b = Domain::try_from("www.example.com.").unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.cmp(&b), core::cmp::Ordering::Equal);
+    assert_eq!(a.stable_hash(), b.stable_hash());
+
+    #[cfg(feature = "serde")]
+    assert_eq!(serde_json::to_string(&b).unwrap(), "\"www.example.com\"");
+  }
+
+  #[test]
+  fn test_was_fqdn() {
+    use std::collections::HashSet;
+
+    for (input, fqdn) in [
+      ("example.com", false),
+      ("example.com.", true),
+      ("测试.com", false),
+      ("测试.com.", true),
+      ("a-very-long-subdomain.example.com", false),
+      ("a-very-long-subdomain.example.com.", true),
+    ] {
+      let name = Domain::try_from(input).unwrap();
+      assert_eq!(name.was_fqdn(), fqdn, "{input}");
+      assert!(!name.as_str().ends_with('.'));
+      assert!(name.fqdn_str().ends_with('.'));
+    }
+
+    assert!(!Domain::from_ascii_unchecked("example.com").was_fqdn());
+    assert!(Domain::from_ascii_unchecked("example.com.").was_fqdn());
+    assert!(Domain::try_from_strict("example.com.").unwrap().was_fqdn());
+
+    let relative = Domain::try_from("example.com").unwrap();
+    let absolute = Domain::try_from("example.com.").unwrap();
+    assert_eq!(relative.as_str(), absolute.as_str());
+    assert_eq!(relative.fqdn_str(), absolute.fqdn_str());
+    assert_eq!(relative, absolute);
+    let set = HashSet::from([relative, absolute]);
+    assert_eq!(set.len(), 1);
+
+    #[cfg(feature = "rkyv")]
+    {
+      let absolute = Domain::try_from("example.com.").unwrap();
+      let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&absolute).unwrap();
+      let archived = rkyv::access::<ArchivedDomain, rkyv::rancor::Error>(&bytes).unwrap();
+      assert!(*archived == Domain::try_from("example.com").unwrap());
+      let deserialized: Domain = rkyv::deserialize::<_, rkyv::rancor::Error>(archived).unwrap();
+      assert!(deserialized.was_fqdn());
+    }
+  }
+
+  #[test]
+  fn test_mixed_labels() {
+    let expected = Domain::try_from("测试.测试.com").unwrap();
+    for input in [
+      "测试.xn--0zwm56d.com",
+      "xn--0zwm56d.测试.com",
+      "测试.xn--0zwm56d.com.",
+    ] {
+      let name = Domain::try_from(input).unwrap();
+      assert_eq!(name.as_str(), "xn--0zwm56d.xn--0zwm56d.com");
+      assert!(name.as_str().is_ascii());
+      assert_eq!(name.to_unicode(), "测试.测试.com");
+      assert_eq!(name, expected);
+    }
+  }
+
   #[test]
   fn test_non_ascii() {
     let name = Domain::try_from("测试.com.").unwrap();
@@ -409,4 +1148,241 @@ mod tests {
     assert_eq!("xn--0zwm56d.xn--fiqs8s", name.as_str());
     assert_eq!("xn--0zwm56d.xn--fiqs8s.", name.fqdn_str());
   }
+
+  #[test]
+  fn test_to_unicode() {
+    let name = Domain::try_from("测试.com").unwrap();
+    assert_eq!(name.to_unicode(), "测试.com");
+    assert!(matches!(name.to_unicode(), Cow::Owned(_)));
+
+    let name = Domain::try_from("测试.中国.").unwrap();
+    assert_eq!(name.to_unicode(), "测试.中国");
+
+    let name = Domain::try_from("example.com").unwrap();
+    assert_eq!(name.to_unicode(), "example.com");
+    assert!(matches!(name.to_unicode(), Cow::Borrowed(_)));
+
+    // ASCII domains are kept as is, only the ones with Punycode labels are lowercased
+    let name = Domain::try_from("Example.COM").unwrap();
+    assert_eq!(name.to_unicode(), "Example.COM");
+    assert!(matches!(name.to_unicode(), Cow::Borrowed(_)));
+    let name = Domain::from_ascii_unchecked("WWW.Example.com");
+    assert_eq!(name.to_unicode(), "WWW.Example.com");
+    let name = Domain::try_from("Www.xn--0zwm56d.com").unwrap();
+    assert_eq!(name.to_unicode(), "www.测试.com");
+  }
+
+  #[test]
+  fn test_labels() {
+    let name = Domain::try_from("www.example.com.").unwrap();
+    assert_eq!(name.labels().collect::<Vec<_>>(), ["www", "example", "com"]);
+    assert_eq!(
+      name.labels_rev().collect::<Vec<_>>(),
+      ["com", "example", "www"]
+    );
+
+    let name = Domain::try_from("localhost").unwrap();
+    assert_eq!(name.labels().collect::<Vec<_>>(), ["localhost"]);
+    assert_eq!(name.labels_rev().collect::<Vec<_>>(), ["localhost"]);
+
+    let name = Domain::try_from("测试.中国").unwrap();
+    assert_eq!(
+      name.labels().collect::<Vec<_>>(),
+      ["xn--0zwm56d", "xn--fiqs8s"]
+    );
+    assert_eq!(name.labels_rev().count(), 2);
+
+    let root = Domain(SmolStr::new_inline("."), true);
+    assert_eq!(root.labels().count(), 0);
+    assert_eq!(root.labels_rev().count(), 0);
+  }
+
+  #[test]
+  fn test_stable_hash() {
+    let name = Domain::try_from("foobar").unwrap();
+    assert_eq!(name.stable_hash(), 0x85944171f73967e8);
+    assert_eq!(
+      Domain::try_from("www.example.com").unwrap().stable_hash(),
+      Domain::try_from("www.example.com.").unwrap().stable_hash()
+    );
+    assert_ne!(
+      Domain::try_from("www.example.com").unwrap().stable_hash(),
+      Domain::try_from("www.example.org").unwrap().stable_hash()
+    );
+  }
+
+  #[test]
+  fn test_truncate_display() {
+    let name = Domain::try_from("example.com").unwrap();
+    assert_eq!(name.truncate_display(2), "example.com");
+    assert_eq!(name.truncate_display(3), "example.com");
+
+    let name = Domain::try_from("a.b.c.www.example.com.").unwrap();
+    assert_eq!(name.truncate_display(2), "…example.com");
+    assert_eq!(name.truncate_display(3), "…www.example.com");
+    assert_eq!(name.truncate_display(6), "a.b.c.www.example.com");
+    assert_eq!(name.truncate_display(0), "…");
+  }
+
+  #[test]
+  fn test_from_reversed_labels() {
+    let name = Domain::from_reversed_labels(["com", "example", "www"]).unwrap();
+    assert_eq!(name, Domain::try_from("www.example.com").unwrap());
+    assert_eq!(name.fqdn_str(), "www.example.com.");
+
+    let name = Domain::try_from("a.b.c.example.com").unwrap();
+    assert_eq!(
+      Domain::from_reversed_labels(name.labels_rev()).unwrap(),
+      name
+    );
+
+    let name =
+      Domain::from_reversed_labels(vec![String::from("中国"), String::from("测试")]).unwrap();
+    assert_eq!(name, Domain::try_from("测试.中国").unwrap());
+
+    assert!(Domain::from_reversed_labels(Vec::<&str>::new()).is_err());
+    assert!(Domain::from_reversed_labels(["com", "", "www"]).is_err());
+    assert!(Domain::from_reversed_labels(["com", "example.www"]).is_err());
+    assert!(Domain::from_reversed_labels(["com", "-example"]).is_err());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_unicode() {
+    let name = Domain::try_from("测试.com").unwrap();
+
+    let json = serde_json::to_string(&name).unwrap();
+    assert_eq!(json, "\"测试.com\"");
+    assert_eq!(serde_json::from_str::<Domain>(&json).unwrap(), name);
+    assert_eq!(
+      serde_json::from_str::<Domain>("\"xn--0zwm56d.com\"").unwrap(),
+      name
+    );
+
+    let compact = bincode::serialize(&name).unwrap();
+    assert_eq!(compact, bincode::serialize("xn--0zwm56d.com").unwrap());
+    assert_eq!(bincode::deserialize::<Domain>(&compact).unwrap(), name);
+    let unicode = bincode::serialize("测试.com").unwrap();
+    assert_eq!(bincode::deserialize::<Domain>(&unicode).unwrap(), name);
+
+    let name = Domain::try_from("www.example.com").unwrap();
+    assert_eq!(serde_json::to_string(&name).unwrap(), "\"www.example.com\"");
+
+    // Escaped Unicode cannot be borrowed from the input.
+    assert_eq!(
+      serde_json::from_str::<Domain>("\"\\u6d4b\\u8bd5.com\"").unwrap(),
+      Domain::try_from("测试.com").unwrap()
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_round_trip() {
+    for input in ["Example.COM", "xn--a.com", "Www.xn--0zwm56d.com"] {
+      let name = Domain::try_from(input).unwrap();
+      let json = serde_json::to_string(&name).unwrap();
+      assert_eq!(json, format!("{input:?}"));
+      let decoded = serde_json::from_str::<Domain>(&json).unwrap();
+      assert_eq!(decoded, name);
+      assert_eq!(decoded.as_str(), input);
+    }
+  }
+
+  #[test]
+  fn test_to_unicode_invalid_punycode() {
+    let name = Domain::try_from("xn--a.com").unwrap();
+    assert_eq!(name.to_unicode(), "xn--a.com");
+    assert!(matches!(name.to_unicode(), Cow::Borrowed(_)));
+  }
+
+  #[test]
+  fn test_prepend_label() {
+    let com = Domain::try_from("com").unwrap();
+    let name = com
+      .prepend_label("example")
+      .and_then(|name| name.prepend_label("www"))
+      .unwrap();
+    assert_eq!(name, Domain::try_from("www.example.com").unwrap());
+    assert_eq!(name.fqdn_str(), "www.example.com.");
+
+    assert_eq!(
+      com.prepend_label("测试").unwrap(),
+      Domain::try_from("测试.com").unwrap()
+    );
+    assert!(Domain::try_from("example.com.")
+      .unwrap()
+      .prepend_label("www")
+      .unwrap()
+      .was_fqdn());
+
+    for label in ["a.b", "", "-www", "w w", "a\u{3002}b", &"a".repeat(64)] {
+      assert!(com.prepend_label(label).is_err(), "{label:?}");
+    }
+  }
+
+  #[test]
+  fn test_join() {
+    let www = Domain::try_from("www").unwrap();
+    let example = Domain::try_from("example.com.").unwrap();
+    let name = www.join(&example).unwrap();
+    assert_eq!(name, Domain::try_from("www.example.com").unwrap());
+    assert!(name.was_fqdn());
+    assert!(!example.join(&www).unwrap().was_fqdn());
+
+    let long = Domain::try_from(format!("{0}.{0}.{0}", "a".repeat(63))).unwrap();
+    assert!(long.join(&long).is_err());
+  }
+
+  #[cfg(feature = "psl")]
+  #[test]
+  fn test_public_suffix() {
+    let name = Domain::try_from("foo.example.co.uk").unwrap();
+    assert_eq!(name.public_suffix(), Some("co.uk"));
+    assert_eq!(name.registrable_domain().unwrap().as_str(), "example.co.uk");
+
+    let name = Domain::try_from("co.uk").unwrap();
+    assert_eq!(name.public_suffix(), Some("co.uk"));
+    assert!(name.registrable_domain().is_none());
+
+    // private suffix
+    let name = Domain::try_from("al8n.github.io").unwrap();
+    assert_eq!(name.public_suffix(), Some("github.io"));
+    assert_eq!(
+      name.registrable_domain().unwrap().as_str(),
+      "al8n.github.io"
+    );
+
+    let name = Domain::try_from("github.io").unwrap();
+    assert!(name.registrable_domain().is_none());
+
+    let name = Domain::try_from("com").unwrap();
+    assert_eq!(name.public_suffix(), Some("com"));
+    assert!(name.registrable_domain().is_none());
+
+    let name = Domain::try_from("www.example.com.").unwrap();
+    assert_eq!(name.public_suffix(), Some("com"));
+    assert_eq!(name.registrable_domain().unwrap().as_str(), "example.com");
+  }
+
+  #[test]
+  fn test_punycode_idempotence() {
+    let name = Domain::try_from("xn--0zwm56d.com").unwrap();
+    assert_eq!("xn--0zwm56d.com", name.as_str());
+    assert_eq!("xn--0zwm56d.com.", name.fqdn_str());
+    assert_eq!("测试.com", name.to_unicode());
+
+    let name = Domain::try_from("xn--0zwm56d.com.").unwrap();
+    assert_eq!("xn--0zwm56d.com", name.as_str());
+
+    // An A-label next to a Unicode label goes through the IDNA path,
+    // which must keep the existing A-label untouched.
+    let name = Domain::try_from("xn--0zwm56d.中国").unwrap();
+    assert_eq!("xn--0zwm56d.xn--fiqs8s", name.as_str());
+
+    // Re-parsing the ASCII form of an IDN must be a no-op.
+    let name = Domain::try_from("测试.com").unwrap();
+    let reparsed = Domain::try_from(name.as_str()).unwrap();
+    assert_eq!(name, reparsed);
+    assert_eq!("xn--0zwm56d.com", reparsed.as_str());
+  }
 }
@@ -6,7 +6,7 @@ use core::{
 mod impls;
 use cheap_clone::CheapClone;
 #[cfg(any(feature = "std", feature = "alloc"))]
-pub use impls::{NodeId, ParseNodeIdError};
+pub use impls::{CaseInsensitiveNodeId, NodeId, ParseNodeIdError, RedactedNodeId};
 
 /// Id abstraction for distributed systems
 pub trait Id: CheapClone + Eq + Ord + Hash + Debug + Display + Sized + Unpin + 'static {}
@@ -2,3 +2,8 @@
 mod id;
 #[cfg(any(feature = "std", feature = "alloc"))]
 pub use id::*;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod case_insensitive;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use case_insensitive::*;
@@ -0,0 +1,162 @@
+use core::{
+  cmp::Ordering,
+  hash::{Hash, Hasher},
+};
+
+use super::NodeId;
+
+/// A [`NodeId`] wrapper which compares, orders and hashes ids ignoring ASCII case,
+/// e.g. `Node-A` and `node-a` are treated as the same id.
+///
+/// The original casing is preserved and returned by [`as_str`](CaseInsensitiveNodeId::as_str).
+///
+/// **N.B.** `Borrow<str>` is intentionally not implemented, because `str` compares and
+/// hashes case-sensitively, which would break the contract of [`Borrow`](core::borrow::Borrow).
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashSet;
+/// use nodecraft::{CaseInsensitiveNodeId, NodeId};
+///
+/// let mut set = HashSet::new();
+/// set.insert(CaseInsensitiveNodeId::from(NodeId::<16>::new("Node-A").unwrap()));
+/// assert!(set.contains(&CaseInsensitiveNodeId::from(NodeId::<16>::new("node-a").unwrap())));
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct CaseInsensitiveNodeId<const N: usize = { u8::MAX as usize }>(NodeId<N>);
+
+impl<const N: usize> CaseInsensitiveNodeId<N> {
+  /// Returns the inner [`NodeId`].
+  #[inline]
+  pub const fn node_id(&self) -> &NodeId<N> {
+    &self.0
+  }
+
+  /// Consumes the wrapper and returns the inner [`NodeId`].
+  #[inline]
+  pub fn into_inner(self) -> NodeId<N> {
+    self.0
+  }
+
+  /// Returns the id as a `&str`, with the original casing.
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    self.0.as_str()
+  }
+}
+
+impl<const N: usize> From<NodeId<N>> for CaseInsensitiveNodeId<N> {
+  #[inline]
+  fn from(id: NodeId<N>) -> Self {
+    Self(id)
+  }
+}
+
+impl<const N: usize> From<CaseInsensitiveNodeId<N>> for NodeId<N> {
+  #[inline]
+  fn from(id: CaseInsensitiveNodeId<N>) -> Self {
+    id.0
+  }
+}
+
+impl<const N: usize> AsRef<str> for CaseInsensitiveNodeId<N> {
+  #[inline]
+  fn as_ref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl<const N: usize> PartialEq for CaseInsensitiveNodeId<N> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.0.eq_ignore_ascii_case(&other.0)
+  }
+}
+
+impl<const N: usize> Eq for CaseInsensitiveNodeId<N> {}
+
+impl<const N: usize> PartialOrd for CaseInsensitiveNodeId<N> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<const N: usize> Ord for CaseInsensitiveNodeId<N> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .as_str()
+      .bytes()
+      .map(|b| b.to_ascii_lowercase())
+      .cmp(other.as_str().bytes().map(|b| b.to_ascii_lowercase()))
+  }
+}
+
+impl<const N: usize> Hash for CaseInsensitiveNodeId<N> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    for b in self.as_str().bytes() {
+      state.write_u8(b.to_ascii_lowercase());
+    }
+    // the same terminator as `str`, so that `("ab", "c")` and `("a", "bc")` hash differently
+    state.write_u8(0xff);
+  }
+}
+
+impl<const N: usize> core::fmt::Display for CaseInsensitiveNodeId<N> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl<const N: usize> core::fmt::Debug for CaseInsensitiveNodeId<N> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl<const N: usize> cheap_clone::CheapClone for CaseInsensitiveNodeId<N> {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_case_insensitive() {
+    use std::collections::HashSet;
+
+    let upper = NodeId::<16>::new("Node-A").unwrap();
+    let lower = NodeId::<16>::new("node-a").unwrap();
+    assert_ne!(upper, lower);
+    assert!(upper.eq_ignore_ascii_case(&lower));
+
+    let mut set = HashSet::new();
+    set.insert(upper.clone());
+    set.insert(lower.clone());
+    assert_eq!(set.len(), 2);
+
+    let mut set = HashSet::new();
+    set.insert(CaseInsensitiveNodeId::from(upper.clone()));
+    set.insert(CaseInsensitiveNodeId::from(lower.clone()));
+    assert_eq!(set.len(), 1);
+    assert!(set.contains(&CaseInsensitiveNodeId::from(NodeId::new("NODE-a").unwrap())));
+    // the casing of the first inserted id is kept
+    assert_eq!(set.iter().next().unwrap().as_str(), "Node-A");
+
+    let a = CaseInsensitiveNodeId::from(NodeId::<16>::new("a").unwrap());
+    let b = CaseInsensitiveNodeId::from(NodeId::<16>::new("B").unwrap());
+    assert!(a < b);
+    assert_eq!(
+      CaseInsensitiveNodeId::from(upper.clone()).cmp(&CaseInsensitiveNodeId::from(lower)),
+      Ordering::Equal
+    );
+    assert_eq!(
+      NodeId::from(CaseInsensitiveNodeId::from(upper.clone())),
+      upper
+    );
+  }
+}
@@ -30,6 +30,14 @@ pub enum ParseNodeIdError {
   /// Returned when the id is not a valid utf8 string.
   #[error(transparent)]
   Utf8Error(#[from] core::str::Utf8Error),
+  /// Returned when the id contains a character outside of the allowed charset.
+  #[error("invalid character {ch:?} at index {index}")]
+  InvalidCharacter {
+    /// The byte index of the invalid character.
+    index: usize,
+    /// The invalid character.
+    ch: char,
+  },
 }
 
 impl ParseNodeIdError {
@@ -42,7 +50,7 @@ impl ParseNodeIdError {
 /// A unique string identifying a server for all time.
 /// The maximum length of an id is 512 bytes.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "serde", serde(transparent))]
 #[cfg_attr(
   feature = "rkyv",
@@ -74,16 +82,109 @@ impl<const N: usize> NodeId<N> {
     Ok(Self(SmolStr::new(src)))
   }
 
+  /// Creates a new `Id` from the source, only allowing the `[A-Za-z0-9_-]` charset,
+  /// so that the id is safe to be used in file names and URLs.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::{NodeId, ParseNodeIdError};
+  ///
+  /// let id = NodeId::<16>::new_ascii_restricted("node-1_a").unwrap();
+  /// assert_eq!(id.as_str(), "node-1_a");
+  ///
+  /// let err = NodeId::<16>::new_ascii_restricted("my node").unwrap_err();
+  /// assert!(matches!(err, ParseNodeIdError::InvalidCharacter { index: 2, ch: ' ' }));
+  /// ```
+  pub fn new_ascii_restricted<T: AsRef<str>>(src: T) -> Result<Self, ParseNodeIdError> {
+    let src = src.as_ref();
+    if let Some((index, ch)) = src
+      .char_indices()
+      .find(|(_, ch)| !(ch.is_ascii_alphanumeric() || *ch == '_' || *ch == '-'))
+    {
+      return Err(ParseNodeIdError::InvalidCharacter { index, ch });
+    }
+
+    Self::new(src)
+  }
+
   /// converts the `Id` into a `&str`.
   pub fn as_str(&self) -> &str {
     self.0.as_ref()
   }
 
+  /// Returns `true` if the two ids are equal, ignoring ASCII case.
+  ///
+  /// See [`CaseInsensitiveNodeId`](crate::CaseInsensitiveNodeId) for a wrapper which
+  /// can be used as a case-insensitive map key.
+  #[inline]
+  pub fn eq_ignore_ascii_case(&self, other: &Self) -> bool {
+    self.as_str().eq_ignore_ascii_case(other.as_str())
+  }
+
+  /// Returns a hash of the id which is stable across processes, platforms and releases,
+  /// e.g. for consistent hashing.
+  ///
+  /// The hash is the 64-bit FNV-1a of the bytes of the id.
+  #[inline]
+  pub fn stable_hash(&self) -> u64 {
+    crate::fnv1a64(self.as_bytes())
+  }
+
   /// Returns a byte slice.
   /// To convert the byte slice back into a string slice, use the [`core::str::from_utf8`] function.
   pub fn as_bytes(&self) -> &[u8] {
     self.0.as_bytes()
   }
+
+  /// Returns a displayable form of the id which does not leak it into logs, see [`RedactedNodeId`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::NodeId;
+  ///
+  /// let id = NodeId::<32>::new("node-s3cr3t-t0k3n").unwrap();
+  /// let redacted = id.redacted().to_string();
+  /// assert_eq!(redacted, "node***");
+  /// assert!(!redacted.contains("s3cr3t"));
+  /// ```
+  #[inline]
+  pub const fn redacted(&self) -> RedactedNodeId<'_, N> {
+    RedactedNodeId(self)
+  }
+}
+
+/// The redacted form of a [`NodeId`], returned by [`NodeId::redacted`].
+///
+/// It displays the first few characters of the id followed by `***`, e.g. `node***`.
+/// At most a quarter of the characters are kept, and never more than four, so short ids
+/// are mostly or fully masked. Nothing else is derived from the rest of the id, which may
+/// be a low-entropy secret.
+///
+/// With the `redact-debug` feature, the [`Debug`](core::fmt::Debug) output of [`NodeId`]
+/// is redacted as well.
+#[derive(Clone, Copy)]
+pub struct RedactedNodeId<'a, const N: usize>(&'a NodeId<N>);
+
+impl<const N: usize> RedactedNodeId<'_, N> {
+  /// The maximum number of characters kept from the start of the id.
+  const PREFIX: usize = 4;
+}
+
+impl<const N: usize> core::fmt::Display for RedactedNodeId<'_, N> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let id = self.0.as_str();
+    let keep = (id.chars().count() / 4).min(Self::PREFIX);
+    let end = id.char_indices().nth(keep).map_or(id.len(), |(end, _)| end);
+    write!(f, "{}***", &id[..end])
+  }
+}
+
+impl<const N: usize> core::fmt::Debug for RedactedNodeId<'_, N> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    core::fmt::Display::fmt(self, f)
+  }
 }
 
 impl<const N: usize> core::str::FromStr for NodeId<N> {
@@ -106,6 +207,21 @@ impl<const N: usize> AsRef<str> for NodeId<N> {
   }
 }
 
+impl<const N: usize> AsRef<[u8]> for NodeId<N> {
+  fn as_ref(&self) -> &[u8] {
+    self.as_str().as_bytes()
+  }
+}
+
+/// Consistent with [`Borrow<str>`], as ids compare, order and hash the same as their strings.
+impl<const N: usize> core::ops::Deref for NodeId<N> {
+  type Target = str;
+
+  fn deref(&self) -> &str {
+    self.as_str()
+  }
+}
+
 impl<const N: usize> core::fmt::Display for NodeId<N> {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     self.0.fmt(f)
@@ -113,9 +229,15 @@ impl<const N: usize> core::fmt::Display for NodeId<N> {
 }
 
 impl<const N: usize> core::fmt::Debug for NodeId<N> {
+  #[cfg(not(feature = "redact-debug"))]
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     self.0.fmt(f)
   }
+
+  #[cfg(feature = "redact-debug")]
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    self.redacted().fmt(f)
+  }
 }
 
 impl<const N: usize> cheap_clone::CheapClone for NodeId<N> {}
@@ -134,6 +256,59 @@ impl<const N: usize> TryFrom<&[u8]> for NodeId<N> {
   }
 }
 
+impl<const N: usize> TryFrom<char> for NodeId<N> {
+  type Error = ParseNodeIdError;
+
+  fn try_from(value: char) -> Result<Self, Self::Error> {
+    let mut buf = [0u8; 4];
+    Self::new(value.encode_utf8(&mut buf))
+  }
+}
+
+#[cfg(feature = "uuid")]
+const _: () = {
+  use uuid::{fmt::Hyphenated, Uuid};
+
+  impl<const N: usize> From<Uuid> for NodeId<N> {
+    /// Formats the UUID in its hyphenated form, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`,
+    /// without an intermediate `String`.
+    ///
+    /// Fails to compile if `N` is less than 36.
+    fn from(uuid: Uuid) -> Self {
+      const {
+        assert!(
+          N >= Hyphenated::LENGTH,
+          "NodeId is too small to hold a UUID"
+        )
+      };
+
+      let mut buf = [0u8; Hyphenated::LENGTH];
+      Self(SmolStr::new(uuid.hyphenated().encode_lower(&mut buf)))
+    }
+  }
+
+  impl<const N: usize> NodeId<N> {
+    /// Parses the id as a UUID, returns `None` if the id is not a valid UUID.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nodecraft::NodeId;
+    /// use uuid::Uuid;
+    ///
+    /// let uuid = Uuid::from_u128(0x67e5504410b1426f9247bb680e5fe0c8);
+    /// let id = NodeId::<64>::from(uuid);
+    /// assert_eq!(id.as_str(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+    /// assert_eq!(id.as_uuid(), Some(uuid));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "uuid")))]
+    #[inline]
+    pub fn as_uuid(&self) -> Option<Uuid> {
+      Uuid::try_parse(self.as_str()).ok()
+    }
+  }
+};
+
 #[cfg(feature = "alloc")]
 impl<const N: usize> TryFrom<Vec<u8>> for NodeId<N> {
   type Error = ParseNodeIdError;
@@ -170,6 +345,111 @@ impl<const N: usize> TryFrom<String> for NodeId<N> {
   }
 }
 
+#[cfg(feature = "serde")]
+const _: () = {
+  use serde::de::{self, Deserialize, Deserializer, Visitor};
+
+  struct NodeIdVisitor<const N: usize>;
+
+  #[cfg(feature = "serde-lenient")]
+  impl<const N: usize> NodeIdVisitor<N> {
+    fn from_integer<E: de::Error>(v: impl core::fmt::Display) -> Result<NodeId<N>, E> {
+      use smol_str03::ToSmolStr;
+
+      NodeId::new(v.to_smolstr()).map_err(E::custom)
+    }
+  }
+
+  impl<const N: usize> Visitor<'_> for NodeIdVisitor<N> {
+    type Value = NodeId<N>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+      if cfg!(feature = "serde-lenient") {
+        f.write_str("a string or an integer")
+      } else {
+        f.write_str("a string")
+      }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+      NodeId::new(v).map_err(E::custom)
+    }
+
+    #[cfg(feature = "serde-lenient")]
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+      Self::from_integer(v)
+    }
+
+    #[cfg(feature = "serde-lenient")]
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+      Self::from_integer(v)
+    }
+
+    #[cfg(feature = "serde-lenient")]
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+      Self::from_integer(v)
+    }
+
+    #[cfg(feature = "serde-lenient")]
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+      Self::from_integer(v)
+    }
+  }
+
+  /// Strings are validated the same way as [`NodeId::new`].
+  ///
+  /// With the `serde-lenient` feature, integers are accepted as well by human-readable
+  /// formats, and are converted to their decimal string form, e.g. `42` is deserialized
+  /// as `"42"`. Binary formats, which are not self-describing, still expect a string.
+  impl<'de, const N: usize> Deserialize<'de> for NodeId<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      if cfg!(feature = "serde-lenient") && deserializer.is_human_readable() {
+        deserializer.deserialize_any(NodeIdVisitor)
+      } else {
+        deserializer.deserialize_str(NodeIdVisitor)
+      }
+    }
+  }
+};
+
+/// Truncates an arbitrary string into an id of at most `N` bytes.
+#[cfg(any(feature = "arbitrary", feature = "quickcheck"))]
+fn arbitrary_id<const N: usize>(mut s: String) -> Result<NodeId<N>, ParseNodeIdError> {
+  if s.len() > N {
+    let end = (0..=N).rev().find(|i| s.is_char_boundary(*i)).unwrap_or(0);
+    s.truncate(end);
+  }
+  if s.is_empty() {
+    s.push('0');
+  }
+  NodeId::new(s)
+}
+
+#[cfg(feature = "arbitrary")]
+const _: () = {
+  use arbitrary::{Arbitrary, Unstructured};
+
+  impl<'a, const N: usize> Arbitrary<'a> for NodeId<N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+      arbitrary_id(String::arbitrary(u)?).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+  }
+};
+
+#[cfg(feature = "quickcheck")]
+const _: () = {
+  use quickcheck::{Arbitrary, Gen};
+
+  impl<const N: usize> Arbitrary for NodeId<N> {
+    fn arbitrary(g: &mut Gen) -> Self {
+      arbitrary_id(String::arbitrary(g)).expect("NodeId<0> cannot hold any id")
+    }
+  }
+};
+
 #[cfg(test)]
 mod tests {
   use core::str::FromStr;
@@ -194,7 +474,7 @@ mod tests {
   fn test_basic() {
     let id = NodeId::<16>::try_from(b"test".as_slice()).unwrap();
     assert_eq!(id.as_str(), "test");
-    assert_eq!(id.as_ref(), "test");
+    assert_eq!(AsRef::<str>::as_ref(&id), "test");
     assert_eq!(id.as_bytes(), b"test");
     println!("{id}");
     println!("{id:?}");
@@ -210,14 +490,14 @@ mod tests {
   fn test_try_from() {
     let id = NodeId::<16>::try_from(String::from("test")).unwrap();
     assert_eq!(id.as_str(), "test");
-    assert_eq!(id.as_ref(), "test");
+    assert_eq!(AsRef::<str>::as_ref(&id), "test");
     assert!(NodeId::<16>::try_from(String::new()).is_err());
     assert!(NodeId::<512>::try_from("a".repeat(513)).is_err());
 
     let id = NodeId::<16>::try_from(Vec::from("test".as_bytes())).unwrap();
 
     assert_eq!(id.as_str(), "test");
-    assert_eq!(id.as_ref(), "test");
+    assert_eq!(AsRef::<str>::as_ref(&id), "test");
     assert!(NodeId::<16>::try_from(Vec::new()).is_err());
     assert!(NodeId::<512>::try_from("a".repeat(513).into_bytes()).is_err());
 
@@ -225,6 +505,85 @@ mod tests {
     assert_eq!(id.as_str(), "test");
   }
 
+  #[test]
+  fn test_try_from_char() {
+    let id = NodeId::<16>::try_from('A').unwrap();
+    assert_eq!(id.as_str(), "A");
+
+    let id = NodeId::<16>::try_from('测').unwrap();
+    assert_eq!(id.as_str(), "测");
+    assert_eq!(id.as_bytes().len(), 3);
+
+    let id = NodeId::<1>::try_from('a').unwrap();
+    assert_eq!(id.as_str(), "a");
+    assert!(matches!(
+      NodeId::<1>::try_from('测'),
+      Err(ParseNodeIdError::TooLarge {
+        maximum: 1,
+        actual: 3
+      })
+    ));
+  }
+
+  #[test]
+  fn test_new_ascii_restricted() {
+    for src in ["node1", "Node_1", "node-1", "-node", "_", "0"] {
+      let id = NodeId::<16>::new_ascii_restricted(src).unwrap();
+      assert_eq!(id.as_str(), src);
+    }
+
+    assert!(matches!(
+      NodeId::<16>::new_ascii_restricted("my node"),
+      Err(ParseNodeIdError::InvalidCharacter { index: 2, ch: ' ' })
+    ));
+    assert!(matches!(
+      NodeId::<16>::new_ascii_restricted("node🚀1"),
+      Err(ParseNodeIdError::InvalidCharacter {
+        index: 4, ch: '🚀'
+      })
+    ));
+    assert!(matches!(
+      NodeId::<16>::new_ascii_restricted("a/b"),
+      Err(ParseNodeIdError::InvalidCharacter { index: 1, ch: '/' })
+    ));
+    assert!(matches!(
+      NodeId::<16>::new_ascii_restricted(""),
+      Err(ParseNodeIdError::Empty)
+    ));
+    assert!(matches!(
+      NodeId::<4>::new_ascii_restricted("node1"),
+      Err(ParseNodeIdError::TooLarge { .. })
+    ));
+    assert!(NodeId::<16>::new("my node").is_ok());
+  }
+
+  #[test]
+  fn test_stable_hash() {
+    assert_eq!(
+      NodeId::<16>::new("a").unwrap().stable_hash(),
+      0xaf63dc4c8601ec8c
+    );
+    assert_eq!(
+      NodeId::<16>::new("foobar").unwrap().stable_hash(),
+      0x85944171f73967e8
+    );
+    assert_ne!(
+      NodeId::<16>::new("node-a").unwrap().stable_hash(),
+      NodeId::<16>::new("node-b").unwrap().stable_hash()
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "uuid")]
+  fn test_uuid() {
+    let uuid = uuid::Uuid::from_bytes(rand::random());
+    let id = NodeId::<36>::from(uuid);
+    assert_eq!(id.as_str(), uuid.hyphenated().to_string());
+    assert_eq!(id.as_uuid(), Some(uuid));
+
+    assert_eq!(NodeId::<36>::new("hello").unwrap().as_uuid(), None);
+  }
+
   #[test]
   #[cfg(feature = "std")]
   fn test_borrow() {
@@ -236,6 +595,37 @@ mod tests {
     assert!(set.contains("test"));
   }
 
+  #[cfg(feature = "quickcheck")]
+  #[quickcheck_macros::quickcheck]
+  fn fuzzy_arbitrary(id: NodeId<4>) -> bool {
+    !id.is_empty() && id.len() <= 4 && NodeId::<4>::new(id.as_str()).unwrap() == id
+  }
+
+  #[cfg(feature = "arbitrary")]
+  #[test]
+  fn test_arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    for _ in 0..64 {
+      let mut data = [0u8; 64];
+      rand::fill(&mut data[..]);
+      let id = NodeId::<3>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+      assert!(!id.is_empty() && id.len() <= 3);
+    }
+  }
+
+  #[test]
+  fn test_as_bytes_and_deref() {
+    fn bytes(val: &impl AsRef<[u8]>) -> &[u8] {
+      val.as_ref()
+    }
+
+    let id = NodeId::<16>::new("node-1").unwrap();
+    assert_eq!(bytes(&id), b"node-1");
+    assert!(id.starts_with("node"));
+    assert_eq!(&*id, "node-1");
+  }
+
   #[cfg(feature = "serde")]
   #[test]
   fn test_serde() {
@@ -243,5 +633,65 @@ mod tests {
     let s = serde_json::to_string(&id).unwrap();
     let decoded: NodeId = serde_json::from_str(&s).unwrap();
     assert_eq!(id, decoded);
+
+    // the same validation as `NodeId::new`
+    assert!(serde_json::from_str::<NodeId>("\"\"").is_err());
+    assert!(serde_json::from_str::<NodeId<4>>("\"node-1\"").is_err());
+    assert!(bincode::deserialize::<NodeId<4>>(&bincode::serialize("node-1").unwrap()).is_err());
+    #[cfg(not(feature = "serde-lenient"))]
+    assert!(serde_json::from_str::<NodeId>("42").is_err());
+  }
+
+  #[cfg(feature = "serde-lenient")]
+  #[test]
+  fn test_serde_lenient() {
+    let id: NodeId = serde_json::from_str("42").unwrap();
+    assert_eq!(id.as_str(), "42");
+    let id: NodeId = serde_json::from_str("-42").unwrap();
+    assert_eq!(id.as_str(), "-42");
+    let id: NodeId = serde_json::from_str("\"node-1\"").unwrap();
+    assert_eq!(id.as_str(), "node-1");
+
+    // the length is still validated
+    assert!(serde_json::from_str::<NodeId<1>>("42").is_err());
+    assert!(serde_json::from_str::<NodeId>("\"\"").is_err());
+    assert!(serde_json::from_str::<NodeId>("4.2").is_err());
+
+    // binary formats are not self-describing, and still work
+    let id = NodeId::<16>::new("node-1").unwrap();
+    let encoded = bincode::serialize(&id).unwrap();
+    assert_eq!(bincode::deserialize::<NodeId<16>>(&encoded).unwrap(), id);
+  }
+
+  #[test]
+  fn test_redacted() {
+    let id = NodeId::<32>::new("node-s3cr3t-t0k3n").unwrap();
+    let redacted = id.redacted().to_string();
+    assert_eq!(redacted, "node***");
+    assert!(!redacted.contains("s3cr3t"));
+    assert_eq!(format!("{:?}", id.redacted()), redacted);
+
+    // short ids reveal at most a quarter of their characters, multi-byte chars are kept whole
+    let secret = "s3cr3t-t0k3n-4b";
+    for len in 1..=secret.len() {
+      let redacted = NodeId::<32>::new(&secret[..len])
+        .unwrap()
+        .redacted()
+        .to_string();
+      let revealed = redacted.strip_suffix("***").unwrap();
+      assert_eq!(revealed, &secret[..len / 4]);
+    }
+    assert_eq!(
+      NodeId::<32>::new("节点标识符标识符")
+        .unwrap()
+        .redacted()
+        .to_string(),
+      "节点***"
+    );
+
+    #[cfg(feature = "redact-debug")]
+    assert_eq!(format!("{id:?}"), redacted);
+    #[cfg(not(feature = "redact-debug"))]
+    assert_eq!(format!("{id:?}"), "\"node-s3cr3t-t0k3n\"");
   }
 }
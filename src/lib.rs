@@ -26,4 +26,23 @@ pub mod resolver;
 #[cfg(feature = "async")]
 pub use futures;
 
+#[cfg(feature = "resolver")]
+pub use smallvec;
+
 pub use cheap_clone::CheapClone;
+
+/// FNV-1a 64-bit hash, used for hashes which must be stable across processes and platforms.
+#[cfg(any(feature = "std", feature = "alloc"))]
+const fn fnv1a64(bytes: &[u8]) -> u64 {
+  const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+  const PRIME: u64 = 0x100000001b3;
+
+  let mut hash = OFFSET_BASIS;
+  let mut i = 0;
+  while i < bytes.len() {
+    hash ^= bytes[i] as u64;
+    hash = hash.wrapping_mul(PRIME);
+    i += 1;
+  }
+  hash
+}
@@ -138,6 +138,127 @@ impl<I, A> Node<I, A> {
     let (id, address) = f(self.id, self.address);
     Node { id, address }
   }
+
+  /// Swaps the id and the address, turning a `Node<I, A>` into a `Node<A, I>`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Node;
+  ///
+  /// let node: Node<u64, u64> = Node::new(1u64, 2u64).swap();
+  /// assert_eq!(node, Node::new(2, 1));
+  /// ```
+  #[inline]
+  pub fn swap(self) -> Node<A, I> {
+    Node {
+      id: self.address,
+      address: self.id,
+    }
+  }
+
+  /// Maps an `Node<I, A>` to `Result<Node<I, U>, E>` by applying a fallible function to the current address.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Node;
+  ///
+  /// let node = Node::new("test", "100");
+  /// let node = node.try_map_address(|address| address.parse::<u64>()).unwrap();
+  /// assert_eq!(node.address(), &100);
+  ///
+  /// let node = Node::new("test", "abc");
+  /// assert!(node.try_map_address(|address| address.parse::<u64>()).is_err());
+  /// ```
+  #[inline]
+  pub fn try_map_address<U, E>(self, f: impl FnOnce(A) -> Result<U, E>) -> Result<Node<I, U>, E> {
+    f(self.address).map(|address| Node {
+      id: self.id,
+      address,
+    })
+  }
+
+  /// Maps an `Node<I, A>` to `Result<Node<U, A>, E>` by applying a fallible function to the current id.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::{Node, NodeId};
+  /// use std::net::SocketAddr;
+  ///
+  /// let addr = "127.0.0.1:8080".parse::<SocketAddr>().unwrap();
+  /// let node = Node::new(String::from("node1"), addr);
+  /// let node = node.try_map_id(NodeId::<64>::try_from).unwrap();
+  /// assert_eq!(node.id().as_str(), "node1");
+  /// ```
+  #[inline]
+  pub fn try_map_id<U, E>(self, f: impl FnOnce(I) -> Result<U, E>) -> Result<Node<U, A>, E> {
+    f(self.id).map(|id| Node {
+      id,
+      address: self.address,
+    })
+  }
+
+  /// Maps an `Node<I, A>` to `Result<Node<U, V>, E>` by applying a fallible function to the current node.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Node;
+  ///
+  /// let node = Node::new("1", "100");
+  ///
+  /// let node = node
+  ///   .try_map(|id, address| {
+  ///     Ok::<_, std::num::ParseIntError>((id.parse::<u64>()?, address.parse::<u64>()?))
+  ///   })
+  ///   .unwrap();
+  ///
+  /// assert_eq!(node.id(), &1);
+  /// assert_eq!(node.address(), &100);
+  /// ```
+  #[inline]
+  pub fn try_map<U, V, E>(
+    self,
+    f: impl FnOnce(I, A) -> Result<(U, V), E>,
+  ) -> Result<Node<U, V>, E> {
+    f(self.id, self.address).map(|(id, address)| Node { id, address })
+  }
+}
+
+impl<I, A> Node<I, A> {
+  /// Attaches metadata to the node.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Node;
+  ///
+  /// let node = Node::new("test", 100u64).attach_meta("v1");
+  /// assert_eq!(node.id(), &"test");
+  /// assert_eq!(node.meta(), &"v1");
+  /// ```
+  #[inline]
+  pub const fn attach_meta<M>(self, meta: M) -> NodeWithMeta<I, A, M> {
+    NodeWithMeta::from_node(self, meta)
+  }
+
+  /// Wraps the node into [`ById`], which compares and hashes only the id.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::Node;
+  ///
+  /// let moved = Node::new("test", 200u64);
+  /// assert_ne!(Node::new("test", 100u64), moved);
+  /// assert_eq!(Node::new("test", 100u64).by_id(), moved.by_id());
+  /// ```
+  #[inline]
+  pub const fn by_id(self) -> ById<I, A> {
+    ById(self)
+  }
 }
 
 impl<I: CheapClone, A: CheapClone> CheapClone for Node<I, A> {
@@ -150,6 +271,238 @@ impl<I: CheapClone, A: CheapClone> CheapClone for Node<I, A> {
   }
 }
 
+/// A [`Node`] with some metadata attached, e.g. the version or the tags of the node.
+///
+/// `NodeWithMeta` dereferences to the inner [`Node`], so the id and address accessors
+/// of [`Node`] can be used directly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+  feature = "rkyv",
+  derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct NodeWithMeta<I, A, M> {
+  node: Node<I, A>,
+  meta: M,
+}
+
+/// A [`Node`] whose equality, ordering and hashing only consider the id, e.g. to key
+/// a membership table by node, where a node which moved to another address is still
+/// the same entity.
+///
+/// `ById` dereferences to the inner [`Node`].
+#[derive(Debug, Copy, Clone)]
+#[repr(transparent)]
+pub struct ById<I, A>(Node<I, A>);
+
+impl<I, A> ById<I, A> {
+  /// Consumes the wrapper and returns the inner [`Node`].
+  #[inline]
+  pub fn into_inner(self) -> Node<I, A> {
+    self.0
+  }
+}
+
+impl<I, A> From<Node<I, A>> for ById<I, A> {
+  #[inline]
+  fn from(node: Node<I, A>) -> Self {
+    Self(node)
+  }
+}
+
+impl<I, A> core::ops::Deref for ById<I, A> {
+  type Target = Node<I, A>;
+
+  #[inline]
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl<I: PartialEq, A> PartialEq for ById<I, A> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.0.id == other.0.id
+  }
+}
+
+impl<I: Eq, A> Eq for ById<I, A> {}
+
+impl<I: PartialOrd, A> PartialOrd for ById<I, A> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+    self.0.id.partial_cmp(&other.0.id)
+  }
+}
+
+impl<I: Ord, A> Ord for ById<I, A> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+    self.0.id.cmp(&other.0.id)
+  }
+}
+
+impl<I: core::hash::Hash, A> core::hash::Hash for ById<I, A> {
+  #[inline]
+  fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+    self.0.id.hash(state)
+  }
+}
+
+impl<I, A, M> core::ops::Deref for NodeWithMeta<I, A, M> {
+  type Target = Node<I, A>;
+
+  #[inline]
+  fn deref(&self) -> &Self::Target {
+    &self.node
+  }
+}
+
+impl<I, A, M> core::ops::DerefMut for NodeWithMeta<I, A, M> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.node
+  }
+}
+
+impl<I, A, M> From<(Node<I, A>, M)> for NodeWithMeta<I, A, M> {
+  #[inline]
+  fn from((node, meta): (Node<I, A>, M)) -> Self {
+    Self { node, meta }
+  }
+}
+
+impl<I, A, M> NodeWithMeta<I, A, M> {
+  /// Create a new node with id, address and metadata.
+  #[inline]
+  pub const fn new(id: I, address: A, meta: M) -> Self {
+    Self {
+      node: Node::new(id, address),
+      meta,
+    }
+  }
+
+  /// Create a new node with metadata from a [`Node`].
+  #[inline]
+  pub const fn from_node(node: Node<I, A>, meta: M) -> Self {
+    Self { node, meta }
+  }
+
+  /// Returns the inner [`Node`].
+  #[inline]
+  pub const fn node(&self) -> &Node<I, A> {
+    &self.node
+  }
+
+  /// Returns the metadata of the node.
+  #[inline]
+  pub const fn meta(&self) -> &M {
+    &self.meta
+  }
+
+  /// Returns a mutable reference to the metadata of the node.
+  #[inline]
+  pub fn meta_mut(&mut self) -> &mut M {
+    &mut self.meta
+  }
+
+  /// Set the metadata of the node.
+  #[inline]
+  pub fn set_meta(&mut self, meta: M) -> &mut Self {
+    self.meta = meta;
+    self
+  }
+
+  /// Set the address of the node. (Builder pattern)
+  #[inline]
+  pub fn with_address(mut self, address: A) -> Self {
+    self.node.address = address;
+    self
+  }
+
+  /// Set the id of the node. (Builder pattern)
+  #[inline]
+  pub fn with_id(mut self, id: I) -> Self {
+    self.node.id = id;
+    self
+  }
+
+  /// Set the metadata of the node. (Builder pattern)
+  #[inline]
+  pub fn with_meta(mut self, meta: M) -> Self {
+    self.meta = meta;
+    self
+  }
+
+  /// Consumes the node and returns the inner [`Node`] and the metadata.
+  #[inline]
+  pub fn into_components(self) -> (Node<I, A>, M) {
+    (self.node, self.meta)
+  }
+
+  /// Consumes the node and returns the inner [`Node`], dropping the metadata.
+  #[inline]
+  pub fn into_node(self) -> Node<I, A> {
+    self.node
+  }
+
+  /// Maps an `NodeWithMeta<I, A, M>` to `NodeWithMeta<I, U, M>` by applying a function to the current address.
+  #[inline]
+  pub fn map_address<U>(self, f: impl FnOnce(A) -> U) -> NodeWithMeta<I, U, M> {
+    NodeWithMeta {
+      node: self.node.map_address(f),
+      meta: self.meta,
+    }
+  }
+
+  /// Maps an `NodeWithMeta<I, A, M>` to `NodeWithMeta<U, A, M>` by applying a function to the current id.
+  #[inline]
+  pub fn map_id<U>(self, f: impl FnOnce(I) -> U) -> NodeWithMeta<U, A, M> {
+    NodeWithMeta {
+      node: self.node.map_id(f),
+      meta: self.meta,
+    }
+  }
+
+  /// Maps an `NodeWithMeta<I, A, M>` to `NodeWithMeta<I, A, U>` by applying a function to the current metadata.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use nodecraft::NodeWithMeta;
+  ///
+  /// let node = NodeWithMeta::new("test", 100u64, 1u64);
+  /// let node = node.map_meta(|meta| meta.to_string());
+  /// assert_eq!(node.meta(), "1");
+  /// ```
+  #[inline]
+  pub fn map_meta<U>(self, f: impl FnOnce(M) -> U) -> NodeWithMeta<I, A, U> {
+    NodeWithMeta {
+      node: self.node,
+      meta: f(self.meta),
+    }
+  }
+
+  /// Maps an `NodeWithMeta<I, A, M>` to `NodeWithMeta<U, V, M>` by applying a function to the current node.
+  #[inline]
+  pub fn map<U, V>(self, f: impl FnOnce(I, A) -> (U, V)) -> NodeWithMeta<U, V, M> {
+    NodeWithMeta {
+      node: self.node.map(f),
+      meta: self.meta,
+    }
+  }
+}
+
+impl<I: CheapClone, A: CheapClone, M: CheapClone> CheapClone for NodeWithMeta<I, A, M> {
+  #[inline]
+  fn cheap_clone(&self) -> Self {
+    Self {
+      node: self.node.cheap_clone(),
+      meta: self.meta.cheap_clone(),
+    }
+  }
+}
+
 #[cfg(feature = "rkyv")]
 const _: () = {
   use rkyv::Archive;
@@ -255,6 +608,15 @@ const _: () = {
       Ok(Self::new(I::arbitrary(u)?, A::arbitrary(u)?))
     }
   }
+
+  impl<'a, I: Arbitrary<'a>, A: Arbitrary<'a>, M: Arbitrary<'a>> Arbitrary<'a>
+    for NodeWithMeta<I, A, M>
+  {
+    #[inline]
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+      Ok(Self::from_node(Node::arbitrary(u)?, M::arbitrary(u)?))
+    }
+  }
 };
 
 #[cfg(feature = "quickcheck")]
@@ -267,11 +629,20 @@ const _: () = {
       Self::new(I::arbitrary(g), A::arbitrary(g))
     }
   }
+
+  impl<I: Arbitrary, A: Arbitrary, M: Arbitrary> Arbitrary for NodeWithMeta<I, A, M> {
+    #[inline]
+    fn arbitrary(g: &mut Gen) -> Self {
+      Self::from_node(Node::arbitrary(g), M::arbitrary(g))
+    }
+  }
 };
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  #[cfg(feature = "std")]
+  use crate::{HostAddr, NodeId};
   use arbitrary::{Arbitrary, Unstructured};
   use rand::distr::Alphanumeric;
   use smol_str03::SmolStr;
@@ -318,6 +689,90 @@ mod tests {
     println!("{}", node);
   }
 
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_try_map() {
+    let node = Node::new(
+      String::from("node1"),
+      "127.0.0.1:8080".parse::<HostAddr>().unwrap(),
+    );
+    let node = node.try_map_id(NodeId::<64>::try_from).unwrap();
+    assert_eq!(node.id().as_str(), "node1");
+
+    let node = Node::new(String::new(), 0u64);
+    assert!(matches!(
+      node.try_map_id(NodeId::<64>::try_from),
+      Err(crate::ParseNodeIdError::Empty)
+    ));
+
+    let node = Node::new("node1", "www.example.com");
+    assert!(node.try_map_address(HostAddr::try_from).is_err());
+
+    let node = Node::new("node1", "www.example.com:80");
+    let node = node
+      .try_map(|id, address| {
+        Ok::<_, crate::ParseHostAddrError>((id.len(), HostAddr::try_from(address)?))
+      })
+      .unwrap();
+    assert_eq!(node.id(), &5);
+    assert_eq!(node.address().port(), 80);
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_node_with_meta() {
+    let mut node = NodeWithMeta::new(
+      NodeId::<16>::new("test").unwrap(),
+      "127.0.0.1:8080".parse::<HostAddr>().unwrap(),
+      1u64,
+    );
+    assert_eq!(node.id().as_str(), "test");
+    assert_eq!(node.address().port(), 8080);
+    assert_eq!(node.meta(), &1);
+
+    let address = node.address().clone().with_port(9090);
+    node.set_address(address);
+    node.set_meta(2);
+    *node.meta_mut() += 1;
+    assert_eq!(node.meta(), &3);
+    assert_eq!(node.address().port(), 9090);
+
+    let node = node
+      .cheap_clone()
+      .with_id(NodeId::new("test2").unwrap())
+      .with_meta(4);
+    assert_eq!(node.id().as_str(), "test2");
+    assert_eq!(node.meta(), &4);
+
+    let node = node
+      .map_id(|id| id.to_string())
+      .map_address(|addr| addr.port())
+      .map_meta(|meta| meta * 2);
+    assert_eq!(node.node(), &Node::new(String::from("test2"), 9090));
+    assert_eq!(node.meta(), &8);
+
+    let (inner, meta) = node.clone().into_components();
+    assert_eq!(NodeWithMeta::from((inner, meta)), node);
+    assert_eq!(
+      Node::new(1u64, 2u64).attach_meta("v1").into_node(),
+      Node::new(1u64, 2u64)
+    );
+  }
+
+  #[cfg(all(feature = "std", feature = "serde"))]
+  #[test]
+  fn test_node_with_meta_serde() {
+    let node = NodeWithMeta::new(
+      NodeId::<16>::new("test").unwrap(),
+      "www.example.com:8080".parse::<HostAddr>().unwrap(),
+      42u64,
+    );
+    let serialized = serde_json::to_string(&node).unwrap();
+    let deserialized: NodeWithMeta<NodeId<16>, HostAddr, u64> =
+      serde_json::from_str(&serialized).unwrap();
+    assert_eq!(node, deserialized);
+  }
+
   #[cfg(feature = "serde")]
   #[test]
   fn test_serde() {
@@ -332,6 +787,34 @@ mod tests {
     assert_eq!(node, deserialized);
   }
 
+  // `Node<u64, u64>` with distinct values, so swapping the id and the address
+  // when encoding or decoding can't go unnoticed.
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_field_order() {
+    let node = Node::new(1u64, 2u64);
+    let serialized = serde_json::to_string(&node).unwrap();
+    let deserialized: Node<u64, u64> = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(*deserialized.id(), 1);
+    assert_eq!(*deserialized.address(), 2);
+  }
+
+  #[cfg(feature = "rkyv")]
+  #[test]
+  fn test_rkyv_field_order() {
+    use rkyv::rancor::Error;
+
+    let node = Node::new(1u64, 2u64);
+    let bytes = rkyv::to_bytes::<Error>(&node).unwrap();
+    let archived = rkyv::access::<ArchivedNode<u64, u64>, Error>(&bytes).unwrap();
+    assert_eq!(archived.id, 1);
+    assert_eq!(archived.address, 2);
+
+    let deserialized: Node<u64, u64> = rkyv::deserialize::<_, Error>(archived).unwrap();
+    assert_eq!(*deserialized.id(), 1);
+    assert_eq!(*deserialized.address(), 2);
+  }
+
   #[cfg(feature = "serde")]
   #[quickcheck_macros::quickcheck]
   fn fuzzy_serde(node: Node<String, u64>) -> bool {
@@ -339,4 +822,44 @@ mod tests {
     let deserialized: Node<String, u64> = serde_json::from_str(&serialized).unwrap();
     node == deserialized
   }
+
+  #[cfg(all(feature = "serde", feature = "quickcheck"))]
+  #[quickcheck_macros::quickcheck]
+  fn fuzzy_serde_node_id(node: Node<NodeId, std::net::SocketAddr>) -> bool {
+    let serialized = serde_json::to_string(&node).unwrap();
+    let compact = bincode::serialize(&node).unwrap();
+    serde_json::from_str::<Node<NodeId, std::net::SocketAddr>>(&serialized).unwrap() == node
+      && bincode::deserialize::<Node<NodeId, std::net::SocketAddr>>(&compact).unwrap() == node
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_by_id() {
+    use std::collections::{BTreeSet, HashMap};
+
+    let node = Node::new(1u64, "127.0.0.1:8080");
+    let moved = Node::new(1u64, "127.0.0.1:9090");
+    let other = Node::new(2u64, "127.0.0.1:8080");
+
+    assert_ne!(node, moved);
+    assert_eq!(node.by_id(), moved.by_id());
+    assert_ne!(node.by_id(), other.by_id());
+    assert!(node.by_id() < other.by_id());
+
+    let mut members = HashMap::new();
+    members.insert(node.by_id(), "alive");
+    members.insert(moved.by_id(), "moved");
+    members.insert(other.by_id(), "alive");
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[&node.by_id()], "moved");
+    // the key keeps the node inserted first
+    let (key, _) = members.get_key_value(&moved.by_id()).unwrap();
+    assert_eq!(key.address(), &"127.0.0.1:8080");
+    assert_eq!(key.into_inner(), node);
+
+    let set = [moved.by_id(), other.by_id(), node.by_id()]
+      .into_iter()
+      .collect::<BTreeSet<_>>();
+    assert_eq!(set.len(), 2);
+  }
 }
@@ -5,6 +5,8 @@ use crate::Address;
 mod impls;
 use cheap_clone::CheapClone;
 pub use impls::*;
+#[cfg(feature = "agnostic")]
+use smallvec::SmallVec;
 
 #[cfg(feature = "agnostic")]
 pub use agnostic::{Runtime, RuntimeLite};
@@ -77,4 +79,70 @@ pub trait AddressResolver: Send + Sync + 'static {
     &self,
     address: &Self::Address,
   ) -> impl Future<Output = Result<Self::ResolvedAddress, Self::Error>> + Send;
+
+  /// Resolves the given node address to all the addresses it refers to.
+  ///
+  /// The returned addresses are never empty. The default implementation only returns
+  /// the address returned by [`resolve`](AddressResolver::resolve).
+  fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> impl Future<Output = Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error>> + Send {
+    let fut = self.resolve(address);
+    async move { fut.await.map(|addr| smallvec::smallvec![addr]) }
+  }
+}
+
+/// Extension trait which allows an [`Address`] to be resolved from the address side.
+///
+/// # Example
+///
+/// ```no_run
+/// use nodecraft::{
+///   resolver::{address::HostAddrResolver, AddressResolver, ResolveExt},
+///   HostAddr,
+/// };
+///
+/// # async fn run() {
+/// let resolver = HostAddrResolver::<agnostic::tokio::TokioRuntime>::default();
+/// let addr = HostAddr::try_from("localhost:8080").unwrap();
+/// let resolved = addr.resolve(&resolver).await.unwrap();
+/// # }
+/// ```
+pub trait ResolveExt: Address {
+  /// Resolves this address with the given resolver.
+  fn resolve<R>(
+    &self,
+    resolver: &R,
+  ) -> impl Future<Output = Result<R::ResolvedAddress, R::Error>> + Send
+  where
+    R: AddressResolver<Address = Self>,
+  {
+    resolver.resolve(self)
+  }
+}
+
+impl<A: Address> ResolveExt for A {}
+
+#[cfg(test)]
+mod tests {
+  use super::{address::HostAddrResolver, *};
+  use crate::HostAddr;
+
+  #[tokio::test]
+  async fn test_resolve_ext() {
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = HostAddrResolver::<TokioRuntime>::default();
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    let resolved = addr.resolve(&resolver).await.unwrap();
+    assert_eq!(resolved, resolver.resolve(&addr).await.unwrap());
+    assert_eq!(resolved.port(), 8080);
+
+    let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+    assert_eq!(
+      addr.resolve(&resolver).await.unwrap(),
+      "127.0.0.1:8080".parse::<std::net::SocketAddr>().unwrap()
+    );
+  }
 }
@@ -14,24 +14,427 @@ pub mod socket_addr;
 #[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "async"))))]
 pub mod address;
 
+/// Blocking [`HostAddr`](crate::HostAddr) resolver, which does not require an async runtime
+/// and only uses [`ToSocketAddrs`](std::net::ToSocketAddrs) to resolve the address.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod blocking;
+
+/// [`HostAddr`](crate::HostAddr) resolver backed by an in-memory host map,
+/// which never sends DNS queries.
+#[cfg(feature = "agnostic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "agnostic")))]
+pub mod r#static;
+
+/// [`HostAddr`](crate::HostAddr) resolver backed by a hosts file, e.g. `/etc/hosts`,
+/// which never sends DNS queries.
+#[cfg(feature = "agnostic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "agnostic")))]
+pub mod hosts;
+
+/// [`HostAddr`](crate::HostAddr) resolver which caches the addresses resolved
+/// by any other resolver.
+#[cfg(feature = "agnostic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "agnostic")))]
+pub mod caching;
+
+#[cfg(feature = "agnostic")]
+mod fallback;
+#[cfg(feature = "agnostic")]
+pub use fallback::*;
+
+#[cfg(feature = "agnostic")]
+mod merge;
+#[cfg(feature = "agnostic")]
+pub use merge::*;
+
+/// How a resolver picks the address to return when a domain resolves to multiple addresses.
+#[cfg(all(feature = "std", feature = "async"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "async"))))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SelectionPolicy {
+  /// Always returns the first address, which is deterministic.
+  #[default]
+  First,
+  /// Rotates through the cached addresses on successive resolutions of the same domain,
+  /// to spread the load across them.
+  RoundRobin,
+}
+
+/// Observes the cache and the failures of a resolver, e.g. to export metrics.
+///
+/// All the methods do nothing by default. Resolvers without an observer skip the calls
+/// entirely.
+#[cfg(all(feature = "std", feature = "async"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "async"))))]
+pub trait ResolverObserver: Send + Sync + 'static {
+  /// Called when the addresses of a domain are served from the cache.
+  fn on_cache_hit(&self, domain: &crate::Domain) {
+    let _ = domain;
+  }
+
+  /// Called when the addresses of a domain are not cached, before looking it up.
+  fn on_cache_miss(&self, domain: &crate::Domain) {
+    let _ = domain;
+  }
+
+  /// Called when a domain fails to resolve.
+  fn on_resolve_error(&self, domain: &crate::Domain) {
+    let _ = domain;
+  }
+}
+
+/// The optional [`ResolverObserver`] of a resolver.
+#[cfg(all(feature = "std", feature = "async"))]
+#[derive(Clone, Default)]
+struct Observer(Option<std::sync::Arc<dyn ResolverObserver>>);
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl core::fmt::Debug for Observer {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_tuple("Observer")
+      .field(&self.0.as_ref().map(|_| ".."))
+      .finish()
+  }
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl Observer {
+  #[inline]
+  fn cache_hit(&self, domain: &crate::Domain) {
+    if let Some(observer) = &self.0 {
+      observer.on_cache_hit(domain);
+    }
+  }
+
+  #[inline]
+  fn cache_miss(&self, domain: &crate::Domain) {
+    if let Some(observer) = &self.0 {
+      observer.on_cache_miss(domain);
+    }
+  }
+
+  #[inline]
+  fn resolve_error(&self, domain: &crate::Domain) {
+    if let Some(observer) = &self.0 {
+      observer.on_resolve_error(domain);
+    }
+  }
+}
+
+/// The addresses a domain resolves to.
+#[cfg(all(feature = "std", feature = "async"))]
+type IpAddrs = smallvec::SmallVec<[std::net::IpAddr; 4]>;
+
+/// The cached resolutions of a domain, which are never empty and expire together.
 #[cfg(all(feature = "std", feature = "async"))]
 struct CachedSocketAddr {
-  val: std::net::SocketAddr,
+  vals: smallvec::SmallVec<[std::net::SocketAddr; 4]>,
   born: std::time::Instant,
   ttl: std::time::Duration,
+  /// Nanoseconds elapsed since `born` when the entry was last accessed.
+  last_access: core::sync::atomic::AtomicU64,
+  /// The index of the next address for [`SelectionPolicy::RoundRobin`].
+  next: core::sync::atomic::AtomicUsize,
 }
 
 #[cfg(all(feature = "std", feature = "async"))]
 impl CachedSocketAddr {
-  fn new(val: std::net::SocketAddr, ttl: std::time::Duration) -> Self {
+  fn new(vals: smallvec::SmallVec<[std::net::SocketAddr; 4]>, ttl: std::time::Duration) -> Self {
+    debug_assert!(!vals.is_empty());
     Self {
-      val,
+      vals,
       born: std::time::Instant::now(),
       ttl,
+      last_access: core::sync::atomic::AtomicU64::new(0),
+      // the lookup which inserts the entry returns the first address
+      next: core::sync::atomic::AtomicUsize::new(1),
     }
   }
 
   fn is_expired(&self) -> bool {
     self.born.elapsed() > self.ttl
   }
+
+  fn ips(&self) -> IpAddrs {
+    self.vals.iter().map(|addr| addr.ip()).collect()
+  }
+
+  fn select(&self, policy: SelectionPolicy) -> std::net::IpAddr {
+    match policy {
+      SelectionPolicy::First => self.vals[0].ip(),
+      SelectionPolicy::RoundRobin => {
+        let idx = self
+          .next
+          .fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        self.vals[idx % self.vals.len()].ip()
+      }
+    }
+  }
+
+  /// Records a cache hit.
+  fn touch(&self) {
+    let elapsed = u64::try_from(self.born.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    self
+      .last_access
+      .fetch_max(elapsed, core::sync::atomic::Ordering::Relaxed);
+  }
+
+  fn last_access(&self) -> std::time::Instant {
+    self.born
+      + std::time::Duration::from_nanos(
+        self.last_access.load(core::sync::atomic::Ordering::Relaxed),
+      )
+  }
+
+  fn entry(&self, domain: &crate::Domain) -> CacheEntry {
+    CacheEntry {
+      domain: domain.clone(),
+      addrs: self.vals.clone(),
+      born: self.born,
+      last_access: self.last_access(),
+      ttl: self.ttl,
+    }
+  }
+}
+
+/// Looks up `name` in the cache, records the hit and applies `f` to the entry,
+/// evicting the entry instead if it has expired.
+#[cfg(all(feature = "std", feature = "async"))]
+fn cached<T>(
+  cache: &crossbeam_skiplist::SkipMap<crate::Domain, CachedSocketAddr>,
+  name: &crate::Domain,
+  f: impl FnOnce(&CachedSocketAddr) -> T,
+) -> Option<T> {
+  let ent = cache.get(name.as_str())?;
+  let val = ent.value();
+  if val.is_expired() {
+    ent.remove();
+    return None;
+  }
+
+  val.touch();
+  Some(f(val))
+}
+
+/// A snapshot of an entry in the cache of a resolver.
+#[cfg(all(feature = "std", feature = "async"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "async"))))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+  domain: crate::Domain,
+  addrs: smallvec::SmallVec<[std::net::SocketAddr; 4]>,
+  born: std::time::Instant,
+  last_access: std::time::Instant,
+  ttl: std::time::Duration,
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl CacheEntry {
+  /// Returns the domain of the entry.
+  #[inline]
+  pub const fn domain(&self) -> &crate::Domain {
+    &self.domain
+  }
+
+  /// Returns the first cached address.
+  #[inline]
+  pub fn addr(&self) -> std::net::SocketAddr {
+    self.addrs[0]
+  }
+
+  /// Returns all the cached addresses, which is never empty.
+  #[inline]
+  pub fn addrs(&self) -> &[std::net::SocketAddr] {
+    &self.addrs
+  }
+
+  /// Returns when the entry was inserted into the cache.
+  #[inline]
+  pub const fn born(&self) -> std::time::Instant {
+    self.born
+  }
+
+  /// Returns when the entry was last accessed.
+  ///
+  /// This equals [`born`](CacheEntry::born) if the entry has never been hit.
+  #[inline]
+  pub const fn last_access(&self) -> std::time::Instant {
+    self.last_access
+  }
+
+  /// Returns the ttl of the entry.
+  #[inline]
+  pub const fn ttl(&self) -> std::time::Duration {
+    self.ttl
+  }
+
+  /// Returns `true` if the entry has expired.
+  #[inline]
+  pub fn is_expired(&self) -> bool {
+    self.born.elapsed() > self.ttl
+  }
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+type SharedLookup<E> =
+  futures::future::Shared<futures::future::BoxFuture<'static, Result<IpAddrs, std::sync::Arc<E>>>>;
+
+/// Counts the invalidations of a resolver's cache, so that the lookups which were
+/// in flight when it was invalidated do not write their stale answers back.
+#[cfg(all(feature = "std", feature = "async"))]
+#[derive(Clone, Default)]
+struct Generation(std::sync::Arc<std::sync::RwLock<u64>>);
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl Generation {
+  /// Returns the current generation, to be checked before writing to the cache.
+  fn snapshot(&self) -> Snapshot {
+    Snapshot {
+      at: *self.0.read().unwrap(),
+      generation: self.clone(),
+    }
+  }
+
+  /// Starts a new generation, and runs `invalidate` before any write of the previous
+  /// one can happen.
+  fn bump(&self, invalidate: impl FnOnce()) {
+    let mut generation = self.0.write().unwrap();
+    *generation += 1;
+    invalidate();
+  }
+}
+
+/// A generation of a resolver's cache, see [`Generation`].
+#[cfg(all(feature = "std", feature = "async"))]
+struct Snapshot {
+  at: u64,
+  generation: Generation,
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl Snapshot {
+  /// Runs `store` only if the cache has not been invalidated since the snapshot was taken.
+  fn store(&self, store: impl FnOnce()) {
+    let generation = self.generation.0.read().unwrap();
+    if *generation == self.at {
+      store();
+    }
+  }
+}
+
+/// Single-flight registry of the in-flight lookups, so that concurrent
+/// resolutions of the same domain share one query.
+///
+/// The lookup future is owned by the registry rather than by any caller, so
+/// dropping one waiter never cancels the query for the others, and if every
+/// waiter is dropped, the next caller resumes the lookup instead of restarting it.
+///
+/// Invalidating the cache through the registry also forgets the in-flight lookups of the
+/// invalidated domains, and the answers of all the lookups in flight at that time are not
+/// cached once they complete.
+#[cfg(all(feature = "std", feature = "async"))]
+struct Inflight<E> {
+  lookups: std::sync::Arc<crossbeam_skiplist::SkipMap<crate::Domain, (u64, SharedLookup<E>)>>,
+  generation: Generation,
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl<E> Default for Inflight<E> {
+  fn default() -> Self {
+    Self {
+      lookups: Default::default(),
+      generation: Default::default(),
+    }
+  }
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl<E: Send + Sync + 'static> Inflight<E> {
+  /// Joins the in-flight lookup of `name`, or starts a new one with `f`, which must only
+  /// write to the cache through the given [`Snapshot`].
+  ///
+  /// The lookup is removed from the registry once it completes, whether it
+  /// succeeds or not, so that a failed lookup is retried by the next caller.
+  fn join<F>(&self, name: &crate::Domain, f: impl FnOnce(Snapshot) -> F) -> SharedLookup<E>
+  where
+    F: core::future::Future<Output = Result<IpAddrs, E>> + Send + 'static,
+  {
+    use futures::FutureExt;
+
+    let snapshot = self.generation.snapshot();
+    self
+      .lookups
+      .get_or_insert_with(name.clone(), || {
+        let lookups = self.lookups.clone();
+        let name = name.clone();
+        let at = snapshot.at;
+        let fut = f(snapshot);
+        let lookup = async move {
+          let res = fut.await.map_err(std::sync::Arc::new);
+          // after an invalidation, the registry may hold a newer lookup of the same domain
+          if let Some(ent) = lookups.get(&name) {
+            if ent.value().0 == at {
+              ent.remove();
+            }
+          }
+          res
+        }
+        .boxed()
+        .shared();
+        (at, lookup)
+      })
+      .value()
+      .1
+      .clone()
+  }
+
+  /// Returns the current generation of the cache, see [`Snapshot::store`].
+  #[cfg(feature = "dns")]
+  fn snapshot(&self) -> Snapshot {
+    self.generation.snapshot()
+  }
+
+  /// Forgets the in-flight lookup of `name` and runs `invalidate`, so that neither the
+  /// entries removed by it nor the answer of the forgotten lookup end up in the cache.
+  fn invalidate(&self, name: &crate::Domain, invalidate: impl FnOnce()) {
+    self.generation.bump(|| {
+      self.lookups.remove(name);
+      invalidate();
+    });
+  }
+
+  /// Forgets all the in-flight lookups and runs `invalidate`, see [`Inflight::invalidate`].
+  fn clear(&self, invalidate: impl FnOnce()) {
+    self.generation.bump(|| {
+      self.lookups.clear();
+      invalidate();
+    });
+  }
+
+  #[cfg(test)]
+  fn contains(&self, name: &crate::Domain) -> bool {
+    self.lookups.contains_key(name)
+  }
+}
+
+/// Runs `lookup` within `timeout`, if any, returning the error built by `elapsed` if it
+/// does not complete in time.
+#[cfg(feature = "agnostic")]
+async fn with_timeout<R, T, E>(
+  timeout: Option<core::time::Duration>,
+  lookup: impl core::future::Future<Output = Result<T, E>> + Send,
+  elapsed: impl FnOnce() -> E,
+) -> Result<T, E>
+where
+  R: agnostic::RuntimeLite,
+{
+  match timeout {
+    Some(timeout) => R::timeout(timeout, lookup)
+      .await
+      .unwrap_or_else(|_| Err(elapsed())),
+    None => lookup.await,
+  }
 }
@@ -1,8 +1,11 @@
 use core::time::Duration;
-use std::net::SocketAddr;
+use std::{io, net::SocketAddr};
 
-use super::{super::AddressResolver, CachedSocketAddr};
-use crate::{address::Domain, HostAddr, Kind};
+use super::{
+  super::AddressResolver, cached, CachedSocketAddr, IpAddrs, Observer, ResolverObserver,
+  SelectionPolicy,
+};
+use crate::{address::Domain, HostAddr};
 
 use crossbeam_skiplist::SkipMap;
 
@@ -15,6 +18,12 @@ pub struct HostAddrResolverOptions {
     serde(with = "humantime_serde", default = "default_record_ttl")
   )]
   record_ttl: Duration,
+  #[cfg_attr(feature = "serde", serde(default))]
+  selection_policy: SelectionPolicy,
+  #[cfg_attr(feature = "serde", serde(with = "humantime_serde", default))]
+  resolve_timeout: Option<Duration>,
+  #[cfg_attr(feature = "serde", serde(skip))]
+  observer: Observer,
 }
 
 impl Default for HostAddrResolverOptions {
@@ -33,6 +42,9 @@ impl HostAddrResolverOptions {
   pub const fn new() -> Self {
     Self {
       record_ttl: default_record_ttl(),
+      selection_policy: SelectionPolicy::First,
+      resolve_timeout: None,
+      observer: Observer(None),
     }
   }
 
@@ -55,6 +67,132 @@ impl HostAddrResolverOptions {
   pub const fn record_ttl(&self) -> Duration {
     self.record_ttl
   }
+
+  /// Set how to pick the address when a domain resolves to multiple addresses in builder pattern
+  #[inline]
+  pub const fn with_selection_policy(mut self, val: SelectionPolicy) -> Self {
+    self.selection_policy = val;
+    self
+  }
+
+  /// Set how to pick the address when a domain resolves to multiple addresses
+  #[inline]
+  pub fn set_selection_policy(&mut self, val: SelectionPolicy) -> &mut Self {
+    self.selection_policy = val;
+    self
+  }
+
+  /// Returns how to pick the address when a domain resolves to multiple addresses.
+  /// Default is [`SelectionPolicy::First`].
+  #[inline]
+  pub const fn selection_policy(&self) -> SelectionPolicy {
+    self.selection_policy
+  }
+
+  /// Set the timeout of looking up a domain in builder pattern.
+  ///
+  /// The timeout only applies to the lookups, addresses served from the cache are
+  /// returned immediately. `None` means no timeout.
+  #[inline]
+  pub const fn with_resolve_timeout(mut self, val: Option<Duration>) -> Self {
+    self.resolve_timeout = val;
+    self
+  }
+
+  /// Set the timeout of looking up a domain
+  #[inline]
+  pub fn set_resolve_timeout(&mut self, val: Option<Duration>) -> &mut Self {
+    self.resolve_timeout = val;
+    self
+  }
+
+  /// Returns the timeout of looking up a domain. Default is `None`, i.e. no timeout.
+  #[inline]
+  pub const fn resolve_timeout(&self) -> Option<Duration> {
+    self.resolve_timeout
+  }
+
+  /// Set the observer of the cache hits, misses and resolution failures in builder pattern
+  #[inline]
+  pub fn with_observer(mut self, val: Option<std::sync::Arc<dyn ResolverObserver>>) -> Self {
+    self.observer = Observer(val);
+    self
+  }
+
+  /// Set the observer of the cache hits, misses and resolution failures
+  #[inline]
+  pub fn set_observer(&mut self, val: Option<std::sync::Arc<dyn ResolverObserver>>) -> &mut Self {
+    self.observer = Observer(val);
+    self
+  }
+
+  /// Returns the observer of the cache hits, misses and resolution failures. Default is `None`.
+  #[inline]
+  pub fn observer(&self) -> Option<&std::sync::Arc<dyn ResolverObserver>> {
+    self.observer.0.as_ref()
+  }
+}
+
+/// The error returned by [`HostAddrResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum HostAddrResolveError {
+  /// Returned when the domain cannot be resolved to any address.
+  ///
+  /// This is best-effort: the system resolver does not report a missing host with a
+  /// dedicated error, so it is recognized from the error message of the known C libraries
+  /// (glibc, musl, BSD and macOS) or the error code on Windows, and any other failure
+  /// is reported as [`Io`](HostAddrResolveError::Io). A lookup which succeeds without
+  /// any address is always reported as `NotFound`.
+  #[error("failed to resolve {0}")]
+  NotFound(Domain),
+  /// Returned when an I/O error occurs while resolving.
+  #[error(transparent)]
+  Io(#[from] io::Error),
+}
+
+impl HostAddrResolveError {
+  /// Classifies the error returned by [`ToSocketAddrs`](std::net::ToSocketAddrs) when
+  /// looking up `name`.
+  ///
+  /// The system resolver does not report "no such host" with a dedicated
+  /// [`io::ErrorKind`], so the message of the error is inspected.
+  fn from_lookup(name: &Domain, err: io::Error) -> Self {
+    const NOT_FOUND: &[&str] = &[
+      // glibc EAI_NONAME
+      "Name or service not known",
+      // glibc EAI_NODATA
+      "No address associated with hostname",
+      // BSD / macOS EAI_NONAME
+      "nodename nor servname provided, or not known",
+      // musl EAI_NONAME
+      "Name does not resolve",
+      // musl EAI_NODATA
+      "Name has no usable address",
+    ];
+
+    let not_found = err.kind() == io::ErrorKind::NotFound
+      // WSAHOST_NOT_FOUND
+      || (cfg!(windows) && err.raw_os_error() == Some(11001))
+      || {
+        let msg = err.to_string();
+        NOT_FOUND.iter().any(|s| msg.contains(s))
+      };
+
+    if not_found {
+      Self::NotFound(name.clone())
+    } else {
+      Self::Io(err)
+    }
+  }
+
+  /// Rebuilds an owned error from one shared between the waiters of a lookup.
+  #[cfg(feature = "agnostic")]
+  fn from_shared(err: std::sync::Arc<Self>) -> Self {
+    std::sync::Arc::try_unwrap(err).unwrap_or_else(|err| match &*err {
+      Self::NotFound(name) => Self::NotFound(name.clone()),
+      Self::Io(e) => Self::Io(io::Error::new(e.kind(), e.to_string())),
+    })
+  }
 }
 
 pub use resolver::HostAddrResolver;
@@ -63,7 +201,12 @@ pub use resolver::HostAddrResolver;
 mod resolver {
   use super::*;
 
+  use std::sync::Arc;
+
   use agnostic::{net::ToSocketAddrs, RuntimeLite};
+  use smallvec::SmallVec;
+
+  use super::super::{with_timeout, CacheEntry, Inflight, Snapshot};
 
   /// A resolver which supports both `domain:port` and socket address. However,
   /// it will only use [`ToSocketAddrs`](std::net::ToSocketAddrs)
@@ -73,9 +216,12 @@ mod resolver {
   ///   use [`SocketAddrResolver`](crate::resolver::socket_addr::SocketAddrResolver).
   /// - If you want to send DNS queries, you may want to use [`DnsResolver`](crate::resolver::dns::DnsResolver).
   ///
-  /// **N.B.** If a domain contains multiple ip addresses, there is no guarantee that
-  /// which one will be used. Users should make sure that the domain only contains
-  /// one ip address, to make sure that [`AddressResolver`] can work properly.
+  /// **N.B.** If a domain contains multiple ip addresses, the one returned is picked
+  /// according to the [`SelectionPolicy`].
+  ///
+  /// Concurrent resolutions of the same domain share a single lookup, and dropping
+  /// one of the [`resolve`](AddressResolver::resolve) futures does not cancel
+  /// the lookup for the others.
   ///
   /// e.g. valid address format:
   /// 1. `www.example.com:8080` // domain
@@ -83,8 +229,12 @@ mod resolver {
   /// 3. `127.0.0.1:8080` // ipv4
   ///
   pub struct HostAddrResolver<R> {
-    cache: SkipMap<Domain, CachedSocketAddr>,
+    cache: Arc<SkipMap<Domain, CachedSocketAddr>>,
+    inflight: Inflight<HostAddrResolveError>,
     record_ttl: Duration,
+    selection_policy: SelectionPolicy,
+    resolve_timeout: Option<Duration>,
+    observer: Observer,
     _marker: std::marker::PhantomData<R>,
   }
 
@@ -97,7 +247,7 @@ mod resolver {
   impl<R: RuntimeLite> AddressResolver for HostAddrResolver<R> {
     type Address = HostAddr;
     type ResolvedAddress = SocketAddr;
-    type Error = std::io::Error;
+    type Error = HostAddrResolveError;
     type Runtime = R;
     type Options = HostAddrResolverOptions;
 
@@ -105,46 +255,115 @@ mod resolver {
     async fn new(opts: Self::Options) -> Result<Self, Self::Error> {
       Ok(Self {
         record_ttl: opts.record_ttl,
+        selection_policy: opts.selection_policy,
+        resolve_timeout: opts.resolve_timeout,
+        observer: opts.observer,
         cache: Default::default(),
+        inflight: Default::default(),
         _marker: Default::default(),
       })
     }
 
     async fn resolve(&self, address: &Self::Address) -> Result<SocketAddr, Self::Error> {
-      match &address.kind {
-        Kind::Ip(ip) => Ok(SocketAddr::new(*ip, address.port)),
-        Kind::Domain(name) => {
-          // First, check cache
-          if let Some(ent) = self.cache.get(name.as_str()) {
-            let val = ent.value();
-            if !val.is_expired() {
-              return Ok(val.val);
-            } else {
-              ent.remove();
-            }
-          }
+      let name = match address.kind.literal_socket_addr(address.port) {
+        Ok(addr) => return Ok(addr),
+        Err(name) => name,
+      };
+
+      // First, check cache
+      if let Some(ip) = cached(&self.cache, name, |val| val.select(self.selection_policy)) {
+        self.observer.cache_hit(name);
+        return Ok(SocketAddr::new(ip, address.port));
+      }
 
-          // Finally, try to find the socket addr locally
-          let port = address.port;
-          let tsafe = name.clone();
+      // Then, join the in-flight lookup of this domain, or start a new one
+      self
+        .join(name, address.port)
+        .await
+        .map(|ips| SocketAddr::new(ips[0], address.port))
+    }
 
-          let res =
-            ToSocketAddrs::<Self::Runtime>::to_socket_addrs(&(tsafe.as_str(), port)).await?;
+    async fn resolve_all(
+      &self,
+      address: &Self::Address,
+    ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+      let name = match address.kind.literal_socket_addr(address.port) {
+        Ok(addr) => return Ok(smallvec::smallvec![addr]),
+        Err(name) => name,
+      };
+
+      let ips = match cached(&self.cache, name, CachedSocketAddr::ips) {
+        Some(ips) => {
+          self.observer.cache_hit(name);
+          ips
+        }
+        None => self.join(name, address.port).await?,
+      };
+
+      Ok(
+        ips
+          .into_iter()
+          .map(|ip| SocketAddr::new(ip, address.port))
+          .collect(),
+      )
+    }
+  }
 
-          if let Some(addr) = res.into_iter().next() {
-            self
-              .cache
-              .insert(name.clone(), CachedSocketAddr::new(addr, self.record_ttl));
-            return Ok(addr);
-          }
+  impl<R: RuntimeLite> HostAddrResolver<R> {
+    /// Joins the in-flight lookup of this domain, or starts a new one.
+    async fn join(&self, name: &Domain, port: u16) -> Result<IpAddrs, HostAddrResolveError> {
+      self.observer.cache_miss(name);
+      self
+        .inflight
+        .join(name, |snapshot| {
+          let lookup = lookup::<R>(
+            self.cache.clone(),
+            snapshot,
+            name.clone(),
+            port,
+            self.record_ttl,
+          );
+          let name = name.clone();
+          with_timeout::<R, _, _>(self.resolve_timeout, lookup, move || timed_out(&name))
+        })
+        .await
+        .map_err(|e| {
+          self.observer.resolve_error(name);
+          HostAddrResolveError::from_shared(e)
+        })
+    }
+  }
 
-          Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("failed to resolve {}", name.as_str()),
-          ))
-        }
-      }
+  fn timed_out(name: &Domain) -> HostAddrResolveError {
+    HostAddrResolveError::Io(io::Error::new(
+      io::ErrorKind::TimedOut,
+      format!("resolving {name} timed out"),
+    ))
+  }
+
+  async fn lookup<R: RuntimeLite>(
+    cache: Arc<SkipMap<Domain, CachedSocketAddr>>,
+    snapshot: Snapshot,
+    name: Domain,
+    port: u16,
+    record_ttl: Duration,
+  ) -> Result<IpAddrs, HostAddrResolveError> {
+    // Try to find the socket addr locally
+    let addrs = ToSocketAddrs::<R>::to_socket_addrs(&(name.as_str(), port))
+      .await
+      .map_err(|e| HostAddrResolveError::from_lookup(&name, e))?
+      .collect::<smallvec::SmallVec<_>>();
+
+    if !addrs.is_empty() {
+      let cached = CachedSocketAddr::new(addrs, record_ttl);
+      let ips = cached.ips();
+      snapshot.store(|| {
+        cache.insert(name, cached);
+      });
+      return Ok(ips);
     }
+
+    Err(HostAddrResolveError::NotFound(name))
   }
 
   impl<R> HostAddrResolver<R> {
@@ -152,10 +371,39 @@ mod resolver {
     pub fn new(opts: HostAddrResolverOptions) -> Self {
       Self {
         record_ttl: opts.record_ttl,
+        selection_policy: opts.selection_policy,
+        resolve_timeout: opts.resolve_timeout,
+        observer: opts.observer,
         cache: Default::default(),
+        inflight: Default::default(),
         _marker: Default::default(),
       }
     }
+
+    /// Returns a snapshot of the entries in the cache, including the expired ones
+    /// which have not been evicted yet.
+    pub fn cache_entries(&self) -> impl Iterator<Item = CacheEntry> + '_ {
+      self.cache.iter().map(|ent| ent.value().entry(ent.key()))
+    }
+
+    /// Removes the cached addresses of the given domain, so the next resolution of it
+    /// does a fresh lookup.
+    ///
+    /// This is safe to call while the domain is being resolved concurrently: the lookup
+    /// in flight is not joined by the next resolutions, and its answer is not cached.
+    pub fn invalidate(&self, domain: &Domain) {
+      self.inflight.invalidate(domain, || {
+        self.cache.remove(domain.as_str());
+      });
+    }
+
+    /// Removes all the cached addresses.
+    ///
+    /// This is safe to call while domains are being resolved concurrently: the lookups
+    /// in flight are not joined by the next resolutions, and their answers are not cached.
+    pub fn clear_cache(&self) {
+      self.inflight.clear(|| self.cache.clear());
+    }
   }
 
   #[cfg(test)]
@@ -204,6 +452,212 @@ mod resolver {
       let bad_addr = HostAddr::try_from("adasdjkljasidjaosdjaisudnaisudibasd.com:8080").unwrap();
       assert!(resolver.resolve(&bad_addr).await.is_err());
     }
+
+    #[tokio::test]
+    async fn test_last_access() {
+      use agnostic::tokio::TokioRuntime;
+
+      let resolver = HostAddrResolver::<TokioRuntime>::default();
+      let addr = HostAddr::try_from("localhost:8080").unwrap();
+      resolver.resolve(&addr).await.unwrap();
+
+      let ent = resolver.cache_entries().next().unwrap();
+      assert_eq!(ent.domain().as_str(), "localhost");
+      assert_eq!(ent.addr().port(), 8080);
+      assert_eq!(ent.ttl(), default_record_ttl());
+      assert!(!ent.is_expired());
+      assert_eq!(ent.last_access(), ent.born());
+
+      tokio::time::sleep(Duration::from_millis(10)).await;
+      resolver.resolve(&addr).await.unwrap();
+      let hit = resolver.cache_entries().next().unwrap();
+      assert_eq!(hit.born(), ent.born());
+      assert!(hit.last_access() >= ent.born() + Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_timeout() {
+      use agnostic::tokio::TokioRuntime;
+
+      // a lookup which never completes
+      let name = Domain::try_from("localhost").unwrap();
+      let timeout = Duration::from_millis(100);
+      let start = std::time::Instant::now();
+      let res =
+        with_timeout::<TokioRuntime, IpAddrs, _>(Some(timeout), futures::future::pending(), || {
+          timed_out(&name)
+        })
+        .await;
+      match res {
+        Err(HostAddrResolveError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::TimedOut),
+        res => panic!("expected TimedOut, got {res:?}"),
+      }
+      let elapsed = start.elapsed();
+      assert!(elapsed >= timeout && elapsed < timeout * 5, "{elapsed:?}");
+
+      // the timeout does not apply to cache hits
+      let resolver = HostAddrResolver::<TokioRuntime>::new(
+        HostAddrResolverOptions::new().with_resolve_timeout(Some(Duration::ZERO)),
+      );
+      resolver.cache.insert(
+        name,
+        CachedSocketAddr::new(
+          smallvec::smallvec!["127.0.0.1:1".parse().unwrap()],
+          Duration::from_secs(60),
+        ),
+      );
+      let addr = HostAddr::try_from("localhost:8080").unwrap();
+      assert_eq!(
+        resolver.resolve(&addr).await.unwrap(),
+        "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+      );
+
+      // lookups which complete in time are not affected
+      let resolver = HostAddrResolver::<TokioRuntime>::new(
+        HostAddrResolverOptions::new().with_resolve_timeout(Some(Duration::from_secs(10))),
+      );
+      resolver.resolve(&addr).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() {
+      use agnostic::tokio::TokioRuntime;
+
+      let resolver = HostAddrResolver::<TokioRuntime>::default();
+      let addr = HostAddr::try_from("localhost:8080").unwrap();
+      resolver.resolve(&addr).await.unwrap();
+      let ent = resolver.cache_entries().next().unwrap();
+
+      resolver.invalidate(ent.domain());
+      assert_eq!(resolver.cache_entries().count(), 0);
+      tokio::time::sleep(Duration::from_millis(10)).await;
+      resolver.resolve(&addr).await.unwrap();
+      let repopulated = resolver.cache_entries().next().unwrap();
+      assert!(repopulated.born() > ent.born());
+
+      resolver.clear_cache();
+      assert_eq!(resolver.cache_entries().count(), 0);
+      resolver.resolve(&addr).await.unwrap();
+      assert_eq!(resolver.cache_entries().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_inflight() {
+      use agnostic::tokio::TokioRuntime;
+      use futures::FutureExt;
+
+      let resolver = HostAddrResolver::<TokioRuntime>::default();
+      let addr = HostAddr::try_from("localhost:8080").unwrap();
+      let name = Domain::try_from("localhost").unwrap();
+
+      let mut stale = Box::pin(resolver.resolve(&addr));
+      assert!((&mut stale).now_or_never().is_none());
+      resolver.invalidate(&name);
+      assert!(!resolver.inflight.contains(&name));
+
+      // the lookup still completes, but its answer is not written back
+      stale.await.unwrap();
+      assert_eq!(resolver.cache_entries().count(), 0);
+      resolver.resolve(&addr).await.unwrap();
+      assert_eq!(resolver.cache_entries().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+      use agnostic::tokio::TokioRuntime;
+
+      let resolver = HostAddrResolver::<TokioRuntime>::default();
+      let addr = HostAddr::try_from("nodecraft.invalid:8080").unwrap();
+      match resolver.resolve(&addr).await {
+        Err(HostAddrResolveError::NotFound(name)) => {
+          assert_eq!(name.as_str(), "nodecraft.invalid")
+        }
+        res => panic!("expected NotFound, got {res:?}"),
+      }
+
+      let err = HostAddrResolveError::from(io::Error::new(io::ErrorKind::TimedOut, "timeout"));
+      assert!(matches!(err, HostAddrResolveError::Io(_)));
+
+      // the messages of the known C libraries, whichever one the tests run on
+      let name = Domain::try_from("nodecraft.invalid").unwrap();
+      for msg in [
+        "failed to lookup address information: Name or service not known",
+        "failed to lookup address information: Name does not resolve",
+        "failed to lookup address information: nodename nor servname provided, or not known",
+      ] {
+        let err = io::Error::other(msg);
+        assert!(matches!(
+          HostAddrResolveError::from_lookup(&name, err),
+          HostAddrResolveError::NotFound(_)
+        ));
+      }
+      let err = io::Error::other("Try again");
+      assert!(matches!(
+        HostAddrResolveError::from_lookup(&name, err),
+        HostAddrResolveError::Io(_)
+      ));
+    }
+
+    #[tokio::test]
+    async fn test_round_robin() {
+      use agnostic::tokio::TokioRuntime;
+
+      let records: smallvec::SmallVec<[SocketAddr; 4]> = ["10.0.0.1:1", "[fd00::1]:1"]
+        .into_iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+      let name = Domain::try_from("multi.nodecraft.test").unwrap();
+      let addr = HostAddr::from((name.clone(), 9090));
+
+      let resolver = HostAddrResolver::<TokioRuntime>::new(
+        HostAddrResolverOptions::new().with_selection_policy(SelectionPolicy::RoundRobin),
+      );
+      resolver.cache.insert(
+        name.clone(),
+        CachedSocketAddr::new(records.clone(), Duration::from_secs(60)),
+      );
+
+      let mut resolved = Vec::new();
+      for _ in 0..4 {
+        resolved.push(resolver.resolve(&addr).await.unwrap());
+      }
+      assert!(resolved.iter().all(|a| a.port() == 9090));
+      assert_eq!(
+        resolved.iter().map(|a| a.ip()).collect::<Vec<_>>(),
+        [1, 0, 1, 0].map(|i| records[i].ip())
+      );
+    }
+
+    #[tokio::test]
+    async fn test_drop_waiter() {
+      use agnostic::tokio::TokioRuntime;
+      use futures::FutureExt;
+
+      let resolver = HostAddrResolver::<TokioRuntime>::default();
+      let addr = HostAddr::try_from("localhost:8080").unwrap();
+      let other = addr.clone().with_port(9090);
+      let name = Domain::try_from("localhost").unwrap();
+
+      let mut dropped = Box::pin(resolver.resolve(&addr));
+      assert!((&mut dropped).now_or_never().is_none());
+      assert!(resolver.inflight.contains(&name));
+
+      let waiters = futures::future::join(resolver.resolve(&addr), resolver.resolve(&other));
+      drop(dropped);
+      let (a, b) = waiters.await;
+      let (a, b) = (a.unwrap(), b.unwrap());
+      assert_eq!(a.ip(), b.ip());
+      assert_eq!(a.port(), 8080);
+      assert_eq!(b.port(), 9090);
+
+      assert!(!resolver.inflight.contains(&name));
+      assert!(!resolver
+        .cache
+        .get(name.as_str())
+        .unwrap()
+        .value()
+        .is_expired());
+    }
   }
 }
 
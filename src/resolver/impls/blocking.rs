@@ -0,0 +1,78 @@
+use std::{
+  io,
+  net::{SocketAddr, ToSocketAddrs},
+};
+
+use crate::HostAddr;
+
+/// Resolves the given [`HostAddr`] to a [`SocketAddr`] in blocking style.
+///
+/// This does not require an async runtime, and will only use [`ToSocketAddrs`]
+/// to resolve the address, which may block the current thread.
+///
+/// **N.B.** If a domain contains multiple ip addresses, the first one returned
+/// by [`ToSocketAddrs`] will be used.
+///
+/// # Example
+///
+/// ```
+/// use nodecraft::{resolver::blocking, HostAddr};
+///
+/// let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+/// assert_eq!(
+///   blocking::resolve(&addr).unwrap(),
+///   "127.0.0.1:8080".parse::<std::net::SocketAddr>().unwrap()
+/// );
+/// ```
+pub fn resolve(address: &HostAddr) -> io::Result<SocketAddr> {
+  match address.kind.literal_socket_addr(address.port) {
+    Ok(addr) => Ok(addr),
+    Err(name) => (name.as_str(), address.port)
+      .to_socket_addrs()?
+      .next()
+      .ok_or_else(|| {
+        io::Error::new(
+          io::ErrorKind::NotFound,
+          format!("failed to resolve {}", name.as_str()),
+        )
+      }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_resolve() {
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    let resolved = resolve(&addr).unwrap();
+    assert!(resolved.ip().is_loopback());
+    assert_eq!(resolved.port(), 8080);
+
+    let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+    assert_eq!(
+      resolve(&addr).unwrap(),
+      "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+    );
+
+    let addr = HostAddr::try_from("[::1]:8080").unwrap();
+    assert_eq!(
+      resolve(&addr).unwrap(),
+      "[::1]:8080".parse::<SocketAddr>().unwrap()
+    );
+
+    let addr = HostAddr::try_from("[fe80::1%3]:8080").unwrap();
+    assert_eq!(
+      resolve(&addr).unwrap(),
+      "[fe80::1%3]:8080".parse::<SocketAddr>().unwrap()
+    );
+
+    // a domain holding an IP literal, e.g. decoded without validation, is not looked up
+    let addr = HostAddr::from((crate::Domain::from_ascii_unchecked("10.1.2.3"), 8080));
+    assert_eq!(
+      resolve(&addr).unwrap(),
+      "10.1.2.3:8080".parse::<SocketAddr>().unwrap()
+    );
+  }
+}
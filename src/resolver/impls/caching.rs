@@ -0,0 +1,316 @@
+use std::{net::SocketAddr, time::Duration};
+
+use crossbeam_skiplist::SkipMap;
+use smallvec::SmallVec;
+
+use super::{super::AddressResolver, cached, CachedSocketAddr, SelectionPolicy};
+use crate::{Domain, HostAddr};
+
+/// The options used to configure a [`CachingResolver`].
+#[derive(Debug, Clone)]
+pub struct CachingResolverOptions<O> {
+  inner: O,
+  ttl: Duration,
+  selection_policy: SelectionPolicy,
+}
+
+impl<O: Default> Default for CachingResolverOptions<O> {
+  fn default() -> Self {
+    Self::new(O::default())
+  }
+}
+
+impl<O> CachingResolverOptions<O> {
+  /// Create a new options with the options of the inner resolver.
+  #[inline]
+  pub const fn new(inner: O) -> Self {
+    Self {
+      inner,
+      ttl: Duration::from_secs(60),
+      selection_policy: SelectionPolicy::First,
+    }
+  }
+
+  /// Returns the options of the inner resolver.
+  #[inline]
+  pub const fn inner(&self) -> &O {
+    &self.inner
+  }
+
+  /// Set how long the resolved addresses of a domain are cached in builder pattern
+  #[inline]
+  pub const fn with_ttl(mut self, ttl: Duration) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Set how long the resolved addresses of a domain are cached
+  #[inline]
+  pub fn set_ttl(&mut self, ttl: Duration) -> &mut Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Returns how long the resolved addresses of a domain are cached. Default is 1 minute.
+  #[inline]
+  pub const fn ttl(&self) -> Duration {
+    self.ttl
+  }
+
+  /// Set how to pick the address when a domain resolves to multiple addresses in builder pattern
+  #[inline]
+  pub const fn with_selection_policy(mut self, val: SelectionPolicy) -> Self {
+    self.selection_policy = val;
+    self
+  }
+
+  /// Set how to pick the address when a domain resolves to multiple addresses
+  #[inline]
+  pub fn set_selection_policy(&mut self, val: SelectionPolicy) -> &mut Self {
+    self.selection_policy = val;
+    self
+  }
+
+  /// Returns how to pick the address when a domain resolves to multiple addresses.
+  /// Default is [`SelectionPolicy::First`].
+  #[inline]
+  pub const fn selection_policy(&self) -> SelectionPolicy {
+    self.selection_policy
+  }
+}
+
+/// A resolver which caches the addresses resolved by any inner [`AddressResolver`]
+/// of [`HostAddr`]s, e.g. to add caching to a custom resolver.
+///
+/// The whole set of addresses of a domain, as returned by the inner
+/// [`resolve_all`](AddressResolver::resolve_all), is cached for the
+/// [ttl](CachingResolverOptions::with_ttl), regardless of the port, and expires together.
+/// Addresses which are not domains are passed to the inner resolver as is.
+pub struct CachingResolver<R> {
+  inner: R,
+  cache: SkipMap<Domain, CachedSocketAddr>,
+  ttl: Duration,
+  selection_policy: SelectionPolicy,
+}
+
+impl<R> CachingResolver<R> {
+  /// Wraps the given resolver, with the options (except the inner ones) of `opts`.
+  pub fn wrap<O>(inner: R, opts: &CachingResolverOptions<O>) -> Self {
+    Self {
+      inner,
+      cache: SkipMap::new(),
+      ttl: opts.ttl,
+      selection_policy: opts.selection_policy,
+    }
+  }
+
+  /// Returns the inner resolver.
+  #[inline]
+  pub const fn inner(&self) -> &R {
+    &self.inner
+  }
+
+  /// Removes the cached addresses of the given domain, so the next resolution of it
+  /// asks the inner resolver.
+  pub fn invalidate(&self, domain: &Domain) {
+    self.cache.remove(domain.as_str());
+  }
+
+  /// Removes all the cached addresses.
+  pub fn clear_cache(&self) {
+    self.cache.clear();
+  }
+}
+
+impl<R> CachingResolver<R>
+where
+  R: AddressResolver<Address = HostAddr, ResolvedAddress = SocketAddr>,
+{
+  /// Asks the inner resolver for all the addresses of `address`, and caches them.
+  async fn lookup(
+    &self,
+    name: &Domain,
+    address: &HostAddr,
+  ) -> Result<SmallVec<[SocketAddr; 4]>, R::Error> {
+    let addrs = self.inner.resolve_all(address).await?;
+    if !addrs.is_empty() {
+      self
+        .cache
+        .insert(name.clone(), CachedSocketAddr::new(addrs.clone(), self.ttl));
+    }
+    Ok(addrs)
+  }
+}
+
+impl<R> AddressResolver for CachingResolver<R>
+where
+  R: AddressResolver<Address = HostAddr, ResolvedAddress = SocketAddr>,
+{
+  type Address = HostAddr;
+  type ResolvedAddress = SocketAddr;
+  type Error = R::Error;
+  type Runtime = R::Runtime;
+  type Options = CachingResolverOptions<R::Options>;
+
+  async fn new(opts: Self::Options) -> Result<Self, Self::Error>
+  where
+    Self: Sized,
+  {
+    let CachingResolverOptions {
+      inner,
+      ttl,
+      selection_policy,
+    } = opts;
+    R::new(inner).await.map(|inner| Self {
+      inner,
+      cache: SkipMap::new(),
+      ttl,
+      selection_policy,
+    })
+  }
+
+  async fn resolve(&self, address: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
+    let Some(name) = address.kind.domain() else {
+      return self.inner.resolve(address).await;
+    };
+
+    if let Some(ip) = cached(&self.cache, name, |val| val.select(self.selection_policy)) {
+      return Ok(SocketAddr::new(ip, address.port));
+    }
+
+    match self.lookup(name, address).await?.first() {
+      Some(addr) => Ok(*addr),
+      // nothing to cache, let the inner resolver decide
+      None => self.inner.resolve(address).await,
+    }
+  }
+
+  async fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+    let Some(name) = address.kind.domain() else {
+      return self.inner.resolve_all(address).await;
+    };
+
+    if let Some(ips) = cached(&self.cache, name, CachedSocketAddr::ips) {
+      return Ok(
+        ips
+          .into_iter()
+          .map(|ip| SocketAddr::new(ip, address.port))
+          .collect(),
+      );
+    }
+
+    self.lookup(name, address).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    io,
+    sync::{
+      atomic::{AtomicUsize, Ordering},
+      Arc,
+    },
+  };
+
+  use agnostic::tokio::TokioRuntime;
+
+  use super::*;
+
+  /// Resolves every domain to `10.0.0.1` and `10.0.0.2`, counting the lookups.
+  #[derive(Default)]
+  struct CountingResolver(Arc<AtomicUsize>);
+
+  impl AddressResolver for CountingResolver {
+    type Address = HostAddr;
+    type ResolvedAddress = SocketAddr;
+    type Error = io::Error;
+    type Runtime = TokioRuntime;
+    type Options = Arc<AtomicUsize>;
+
+    async fn new(lookups: Self::Options) -> Result<Self, Self::Error> {
+      Ok(Self(lookups))
+    }
+
+    async fn resolve(&self, address: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
+      self.resolve_all(address).await.map(|addrs| addrs[0])
+    }
+
+    async fn resolve_all(
+      &self,
+      address: &Self::Address,
+    ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+      self.0.fetch_add(1, Ordering::SeqCst);
+      Ok(smallvec::smallvec![
+        SocketAddr::new("10.0.0.1".parse().unwrap(), address.port()),
+        SocketAddr::new("10.0.0.2".parse().unwrap(), address.port()),
+      ])
+    }
+  }
+
+  #[tokio::test]
+  async fn test_caching_resolver() {
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let resolver =
+      CachingResolver::<CountingResolver>::new(CachingResolverOptions::new(lookups.clone()))
+        .await
+        .unwrap();
+
+    let addr = HostAddr::try_from("www.example.com:80").unwrap();
+    let first = resolver.resolve(&addr).await.unwrap();
+    assert_eq!(first, "10.0.0.1:80".parse::<SocketAddr>().unwrap());
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+    // served from the cache, whatever the port
+    assert_eq!(resolver.resolve(&addr).await.unwrap(), first);
+    assert_eq!(
+      resolver
+        .resolve_all(&addr.clone().with_port(8080))
+        .await
+        .unwrap()
+        .as_slice(),
+      [
+        "10.0.0.1:8080".parse::<SocketAddr>().unwrap(),
+        "10.0.0.2:8080".parse().unwrap()
+      ]
+    );
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+    // ip addresses are not cached
+    let ip = HostAddr::try_from("127.0.0.1:80").unwrap();
+    resolver.resolve(&ip).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+
+    resolver.invalidate(&Domain::try_from("www.example.com").unwrap());
+    resolver.resolve(&addr).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 3);
+  }
+
+  #[tokio::test]
+  async fn test_caching_resolver_ttl() {
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let opts = CachingResolverOptions::new(())
+      .with_ttl(Duration::from_millis(50))
+      .with_selection_policy(SelectionPolicy::RoundRobin);
+    assert_eq!(opts.ttl(), Duration::from_millis(50));
+    let resolver = CachingResolver::wrap(CountingResolver(lookups.clone()), &opts);
+
+    let addr = HostAddr::try_from("www.example.com:80").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.1:80".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.2:80".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    resolver.resolve(&addr).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+  }
+}
@@ -1,17 +1,29 @@
 use core::time::Duration;
-use std::{io, net::SocketAddr};
+use std::{
+  io,
+  net::{IpAddr, SocketAddr},
+  sync::Arc,
+  time::Instant,
+};
 
 pub use agnostic::{
-  dns::{AsyncConnectionProvider, Dns, ResolverConfig, ResolverOpts},
+  dns::{AsyncConnectionProvider, Dns, LookupIpStrategy, ResolverConfig, ResolverOpts},
   net::Net,
 };
+pub use hickory_resolver::name_server::ConnectionProvider;
+
 use agnostic::{net::ToSocketAddrs, Runtime};
 use crossbeam_skiplist::SkipMap;
+use hickory_resolver::AsyncResolver;
+use smallvec::SmallVec;
 
-use super::{super::AddressResolver, CachedSocketAddr};
-use crate::{Domain, HostAddr, Kind};
+use super::{
+  super::AddressResolver, cached, with_timeout, CacheEntry, CachedSocketAddr, Inflight, IpAddrs,
+  Observer, ResolverObserver, SelectionPolicy, Snapshot,
+};
+use crate::{Domain, HostAddr};
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 enum ResolveErrorKind {
   #[error("cannot resolve an ip address for {0}")]
   NotFound(Domain),
@@ -20,7 +32,7 @@ enum ResolveErrorKind {
 }
 
 /// The error type for errors that get returned when resolving fails
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[repr(transparent)]
 pub struct ResolveError(ResolveErrorKind);
 
@@ -38,6 +50,41 @@ impl From<ResolveErrorKind> for ResolveError {
   }
 }
 
+impl ResolveError {
+  /// Returns `true` if the domain has no records, which is negatively cached.
+  fn is_not_found(&self) -> bool {
+    match &self.0 {
+      ResolveErrorKind::NotFound(_) => true,
+      ResolveErrorKind::Resolve(e) => matches!(
+        e.kind(),
+        hickory_resolver::error::ResolveErrorKind::NoRecordsFound { .. }
+      ),
+    }
+  }
+}
+
+/// A domain which does not exist or has no records (NXDOMAIN or NODATA), kept so that
+/// the lookup is not repeated until the negative ttl expires.
+struct CachedError {
+  err: ResolveError,
+  born: Instant,
+  ttl: Duration,
+}
+
+impl CachedError {
+  fn new(err: ResolveError, ttl: Duration) -> Self {
+    Self {
+      err,
+      born: Instant::now(),
+      ttl,
+    }
+  }
+
+  fn is_expired(&self) -> bool {
+    self.born.elapsed() > self.ttl
+  }
+}
+
 /// Errors that can occur when resolving an address.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -47,6 +94,76 @@ pub enum Error {
   /// Returns when there is an error when resolving an address
   #[error(transparent)]
   Resolve(#[from] ResolveError),
+  /// Returns when resolving a domain takes longer than the
+  /// [resolve timeout](DnsResolverOptions::with_resolve_timeout)
+  #[error("resolving {0} timed out")]
+  Timeout(Domain),
+  /// Returns when every attempt to query the name servers for a domain timed out,
+  /// see [`ResolverOpts::attempts`] and [`ResolverOpts::timeout`]
+  #[error("all {attempts} attempts to resolve {domain} timed out")]
+  AllAttemptsFailed {
+    /// The domain which could not be resolved
+    domain: Domain,
+    /// The number of attempts, i.e. the first query and its retries
+    attempts: usize,
+  },
+}
+
+impl Error {
+  /// Recovers the error of a lookup shared by several waiters.
+  fn from_shared(err: Arc<Self>) -> Self {
+    Arc::try_unwrap(err).unwrap_or_else(|err| match &*err {
+      Self::IO(e) => Self::IO(io::Error::new(e.kind(), e.to_string())),
+      Self::Resolve(e) => Self::Resolve(e.clone()),
+      Self::Timeout(name) => Self::Timeout(name.clone()),
+      Self::AllAttemptsFailed { domain, attempts } => Self::AllAttemptsFailed {
+        domain: domain.clone(),
+        attempts: *attempts,
+      },
+    })
+  }
+}
+
+/// Which address families [`DnsResolver`] returns when a domain has both `A` and `AAAA` records.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AddressFamilyPreference {
+  /// Returns the addresses as the [`LookupIpStrategy`] of the [`ResolverOpts`] finds them.
+  #[default]
+  Any,
+  /// Only returns the IPv4 addresses.
+  V4Only,
+  /// Only returns the IPv6 addresses.
+  V6Only,
+  /// Returns the IPv4 addresses before the IPv6 ones.
+  PreferV4,
+  /// Returns the IPv6 addresses before the IPv4 ones.
+  PreferV6,
+}
+
+impl AddressFamilyPreference {
+  /// Returns the strategy used to query the name servers, or `None` to keep the configured one.
+  const fn strategy(&self) -> Option<LookupIpStrategy> {
+    match self {
+      Self::Any => None,
+      Self::V4Only => Some(LookupIpStrategy::Ipv4Only),
+      Self::V6Only => Some(LookupIpStrategy::Ipv6Only),
+      Self::PreferV4 | Self::PreferV6 => Some(LookupIpStrategy::Ipv4AndIpv6),
+    }
+  }
+
+  /// Drops the addresses of the unwanted family, and moves the preferred family first,
+  /// keeping the order of the addresses within a family.
+  fn apply<T>(&self, addrs: &mut SmallVec<[T; 4]>, ip: impl Fn(&T) -> IpAddr) {
+    match self {
+      Self::Any => {}
+      Self::V4Only => addrs.retain(|addr| ip(addr).is_ipv4()),
+      Self::V6Only => addrs.retain(|addr| ip(addr).is_ipv6()),
+      Self::PreferV4 => addrs.sort_by_key(|addr| ip(addr).is_ipv6()),
+      Self::PreferV6 => addrs.sort_by_key(|addr| ip(addr).is_ipv4()),
+    }
+  }
 }
 
 /// The options used to configure the DNS
@@ -61,6 +178,22 @@ const fn default_record_ttl() -> Duration {
   Duration::from_secs(60)
 }
 
+const fn default_min_ttl() -> Duration {
+  Duration::from_secs(1)
+}
+
+const fn default_max_ttl() -> Duration {
+  Duration::from_secs(86400)
+}
+
+const fn default_local_fallback() -> bool {
+  true
+}
+
+const fn default_negative_ttl() -> Duration {
+  Duration::from_secs(5)
+}
+
 impl DnsOptions {
   /// Create a new [`DnsResolverOptions`] with the default DNS configurations.
   pub fn new() -> Self {
@@ -117,7 +250,23 @@ impl Default for DnsOptions {
 pub struct DnsResolverOptions {
   #[cfg_attr(feature = "serde", serde(default = "default_record_ttl"))]
   record_ttl: Duration,
+  #[cfg_attr(feature = "serde", serde(default = "default_min_ttl"))]
+  min_ttl: Duration,
+  #[cfg_attr(feature = "serde", serde(default = "default_max_ttl"))]
+  max_ttl: Duration,
+  #[cfg_attr(feature = "serde", serde(default = "default_negative_ttl"))]
+  negative_ttl: Duration,
   dns: Option<DnsOptions>,
+  #[cfg_attr(feature = "serde", serde(default = "default_local_fallback"))]
+  local_fallback: bool,
+  #[cfg_attr(feature = "serde", serde(default))]
+  selection_policy: SelectionPolicy,
+  #[cfg_attr(feature = "serde", serde(default))]
+  resolve_timeout: Option<Duration>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  address_family: AddressFamilyPreference,
+  #[cfg_attr(feature = "serde", serde(skip))]
+  observer: Observer,
 }
 
 impl Default for DnsResolverOptions {
@@ -132,11 +281,24 @@ impl DnsResolverOptions {
   pub fn new() -> Self {
     Self {
       record_ttl: default_record_ttl(),
+      min_ttl: default_min_ttl(),
+      max_ttl: default_max_ttl(),
+      negative_ttl: default_negative_ttl(),
       dns: Some(DnsOptions::default()),
+      local_fallback: default_local_fallback(),
+      selection_policy: SelectionPolicy::First,
+      resolve_timeout: None,
+      address_family: AddressFamilyPreference::Any,
+      observer: Observer(None),
     }
   }
 
-  /// Set the default record ttl in builder pattern
+  /// Set the default record ttl in builder pattern.
+  ///
+  /// The default record ttl is used for the addresses which carry no ttl, i.e. the ones
+  /// resolved by [`ToSocketAddrs`](std::net::ToSocketAddrs). The addresses returned by
+  /// DNS are cached for the ttl of their records, see [`with_min_ttl`](Self::with_min_ttl)
+  /// and [`with_max_ttl`](Self::with_max_ttl).
   #[inline]
   pub const fn with_record_ttl(mut self, ttl: Duration) -> Self {
     self.record_ttl = ttl;
@@ -156,6 +318,93 @@ impl DnsResolverOptions {
     self.record_ttl
   }
 
+  /// Set the lower bound of the ttl of the DNS records in builder pattern
+  #[inline]
+  pub const fn with_min_ttl(mut self, ttl: Duration) -> Self {
+    self.min_ttl = ttl;
+    self
+  }
+
+  /// Set the lower bound of the ttl of the DNS records
+  #[inline]
+  pub fn set_min_ttl(&mut self, ttl: Duration) -> &mut Self {
+    self.min_ttl = ttl;
+    self
+  }
+
+  /// Returns the lower bound of the ttl of the DNS records. Default is 1 second.
+  #[inline]
+  pub const fn min_ttl(&self) -> Duration {
+    self.min_ttl
+  }
+
+  /// Set the upper bound of the ttl of the DNS records in builder pattern
+  #[inline]
+  pub const fn with_max_ttl(mut self, ttl: Duration) -> Self {
+    self.max_ttl = ttl;
+    self
+  }
+
+  /// Set the upper bound of the ttl of the DNS records
+  #[inline]
+  pub fn set_max_ttl(&mut self, ttl: Duration) -> &mut Self {
+    self.max_ttl = ttl;
+    self
+  }
+
+  /// Returns the upper bound of the ttl of the DNS records. Default is 1 day.
+  #[inline]
+  pub const fn max_ttl(&self) -> Duration {
+    self.max_ttl
+  }
+
+  /// Set how long a domain which does not exist or has no records is remembered in builder pattern.
+  ///
+  /// Within this window, resolving the domain fails immediately without doing any I/O.
+  /// Transient failures, e.g. timeouts, are never cached.
+  /// [`Duration::ZERO`] disables negative caching.
+  #[inline]
+  pub const fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+    self.negative_ttl = ttl;
+    self
+  }
+
+  /// Set how long a domain which does not exist or has no records is remembered
+  #[inline]
+  pub fn set_negative_ttl(&mut self, ttl: Duration) -> &mut Self {
+    self.negative_ttl = ttl;
+    self
+  }
+
+  /// Returns how long a domain which does not exist or has no records is remembered. Default is 5 seconds.
+  #[inline]
+  pub const fn negative_ttl(&self) -> Duration {
+    self.negative_ttl
+  }
+
+  /// Set the timeout of looking up a domain in builder pattern.
+  ///
+  /// The timeout only applies to the lookups, addresses served from the cache are
+  /// returned immediately. `None` means no timeout.
+  #[inline]
+  pub const fn with_resolve_timeout(mut self, timeout: Option<Duration>) -> Self {
+    self.resolve_timeout = timeout;
+    self
+  }
+
+  /// Set the timeout of looking up a domain
+  #[inline]
+  pub fn set_resolve_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+    self.resolve_timeout = timeout;
+    self
+  }
+
+  /// Returns the timeout of looking up a domain. Default is `None`, i.e. no timeout.
+  #[inline]
+  pub const fn resolve_timeout(&self) -> Option<Duration> {
+    self.resolve_timeout
+  }
+
   /// Set the default dns configuration in builder pattern
   #[inline]
   pub fn with_dns(mut self, dns: Option<DnsOptions>) -> Self {
@@ -175,6 +424,93 @@ impl DnsResolverOptions {
   pub const fn dns(&self) -> Option<&DnsOptions> {
     self.dns.as_ref()
   }
+
+  /// Set whether to fall back to [`ToSocketAddrs`](std::net::ToSocketAddrs) when DNS
+  /// returns no record or is disabled in builder pattern
+  #[inline]
+  pub const fn with_local_fallback(mut self, val: bool) -> Self {
+    self.local_fallback = val;
+    self
+  }
+
+  /// Set whether to fall back to [`ToSocketAddrs`](std::net::ToSocketAddrs) when DNS
+  /// returns no record or is disabled
+  #[inline]
+  pub fn set_local_fallback(&mut self, val: bool) -> &mut Self {
+    self.local_fallback = val;
+    self
+  }
+
+  /// Returns whether to fall back to [`ToSocketAddrs`](std::net::ToSocketAddrs) when DNS
+  /// returns no record or is disabled. Default is `true`.
+  #[inline]
+  pub const fn local_fallback(&self) -> bool {
+    self.local_fallback
+  }
+
+  /// Set how to pick the address when a domain resolves to multiple addresses in builder pattern
+  #[inline]
+  pub const fn with_selection_policy(mut self, val: SelectionPolicy) -> Self {
+    self.selection_policy = val;
+    self
+  }
+
+  /// Set how to pick the address when a domain resolves to multiple addresses
+  #[inline]
+  pub fn set_selection_policy(&mut self, val: SelectionPolicy) -> &mut Self {
+    self.selection_policy = val;
+    self
+  }
+
+  /// Returns how to pick the address when a domain resolves to multiple addresses.
+  /// Default is [`SelectionPolicy::First`].
+  #[inline]
+  pub const fn selection_policy(&self) -> SelectionPolicy {
+    self.selection_policy
+  }
+
+  /// Set which address families are returned in builder pattern.
+  ///
+  /// Unless it is [`AddressFamilyPreference::Any`], it overrides the
+  /// [`LookupIpStrategy`] of the [`ResolverOpts`].
+  #[inline]
+  pub const fn with_address_family(mut self, val: AddressFamilyPreference) -> Self {
+    self.address_family = val;
+    self
+  }
+
+  /// Set which address families are returned
+  #[inline]
+  pub fn set_address_family(&mut self, val: AddressFamilyPreference) -> &mut Self {
+    self.address_family = val;
+    self
+  }
+
+  /// Returns which address families are returned. Default is [`AddressFamilyPreference::Any`].
+  #[inline]
+  pub const fn address_family(&self) -> AddressFamilyPreference {
+    self.address_family
+  }
+
+  /// Set the observer of the cache hits, misses and resolution failures in builder pattern
+  #[inline]
+  pub fn with_observer(mut self, val: Option<Arc<dyn ResolverObserver>>) -> Self {
+    self.observer = Observer(val);
+    self
+  }
+
+  /// Set the observer of the cache hits, misses and resolution failures
+  #[inline]
+  pub fn set_observer(&mut self, val: Option<Arc<dyn ResolverObserver>>) -> &mut Self {
+    self.observer = Observer(val);
+    self
+  }
+
+  /// Returns the observer of the cache hits, misses and resolution failures. Default is `None`.
+  #[inline]
+  pub fn observer(&self) -> Option<&Arc<dyn ResolverObserver>> {
+    self.observer.0.as_ref()
+  }
 }
 
 /// A resolver which supports both `domain:port` and socket address.
@@ -183,21 +519,47 @@ impl DnsResolverOptions {
 ///   use [`SocketAddrResolver`](crate::resolver::socket_addr::SocketAddrResolver).
 /// - If you do not want to send DNS queries, you may want to use [`AddressResolver`](crate::resolver::address::AddressResolver).
 ///
-/// **N.B.** If a domain contains multiple ip addresses, there is no guarantee that
-/// which one will be used. Users should make sure that the domain only contains
-/// one ip address, to make sure that [`DnsResolver`] can work properly.
+/// **N.B.** If a domain contains multiple ip addresses, [`resolve`](AddressResolver::resolve)
+/// picks one according to the [`SelectionPolicy`], use [`resolve_all`](AddressResolver::resolve_all)
+/// to get all of them.
+/// The whole set of addresses of a domain is cached and expires together.
+///
+/// Domains which do not exist or have no records (NXDOMAIN or NODATA answers) are remembered
+/// for a [negative ttl](DnsResolverOptions::with_negative_ttl), so that they are not looked up
+/// again and again. Transient failures, e.g. timeouts or I/O errors, are not cached, and the
+/// next resolution retries the lookup.
+///
+/// Concurrent resolutions of the same domain share a single lookup, and dropping
+/// one of the [`resolve`](AddressResolver::resolve) futures does not cancel
+/// the lookup for the others.
 ///
 /// e.g. valid address format:
 /// 1. `www.example.com:8080` // domain
 /// 2. `[::1]:8080` // ipv6
 /// 3. `127.0.0.1:8080` // ipv4
-pub struct DnsResolver<R: Runtime> {
-  dns: Option<Dns<R::Net>>,
-  record_ttl: Duration,
-  cache: SkipMap<Domain, CachedSocketAddr>,
+///
+/// DNS queries are sent through the [`ConnectionProvider`] `P`, which defaults to the
+/// runtime's network, see [`DnsResolver::with_provider`] to use another one.
+pub struct DnsResolver<
+  R: Runtime,
+  P: ConnectionProvider = AsyncConnectionProvider<<R as Runtime>::Net>,
+> {
+  dns: Option<AsyncResolver<P>>,
+  lookup_opts: LookupOptions,
+  selection_policy: SelectionPolicy,
+  cache: Arc<SkipMap<Domain, CachedSocketAddr>>,
+  negative_ttl: Duration,
+  negative_cache: SkipMap<Domain, CachedError>,
+  resolve_timeout: Option<Duration>,
+  observer: Observer,
+  inflight: Inflight<Error>,
+  _marker: std::marker::PhantomData<R>,
+  /// The number of lookups started, for tests.
+  #[cfg(test)]
+  lookups: core::sync::atomic::AtomicUsize,
 }
 
-impl<R: Runtime> AddressResolver for DnsResolver<R> {
+impl<R: Runtime, P: ConnectionProvider + Default> AddressResolver for DnsResolver<R, P> {
   type Address = HostAddr;
   type Error = Error;
   type ResolvedAddress = SocketAddr;
@@ -208,72 +570,322 @@ impl<R: Runtime> AddressResolver for DnsResolver<R> {
   where
     Self: Sized,
   {
-    let dns = if let Some(opts) = opts.dns {
-      Some(Dns::new(
-        opts.resolver_config,
-        opts.resolver_opts,
-        AsyncConnectionProvider::new(),
-      ))
-    } else {
-      None
+    Ok(Self::with_provider(opts, P::default()))
+  }
+
+  async fn resolve(&self, address: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
+    let name = match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => return Ok(addr),
+      Err(name) => name,
+    };
+
+    // First, check cache
+    if let Some(ip) = cached(&self.cache, name, |val| val.select(self.selection_policy)) {
+      self.observer.cache_hit(name);
+      return Ok(SocketAddr::new(ip, address.port));
+    }
+
+    self
+      .join(name, address.port)
+      .await
+      .map(|ips| SocketAddr::new(ips[0], address.port))
+  }
+
+  /// The addresses are in the order returned by the DNS server
+  /// (or [`ToSocketAddrs`](std::net::ToSocketAddrs)), each paired with the port of `address`.
+  async fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+    let name = match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => return Ok(smallvec::smallvec![addr]),
+      Err(name) => name,
     };
-    Ok(Self {
+
+    let ips = match cached(&self.cache, name, CachedSocketAddr::ips) {
+      Some(ips) => {
+        self.observer.cache_hit(name);
+        ips
+      }
+      None => self.join(name, address.port).await?,
+    };
+
+    Ok(
+      ips
+        .into_iter()
+        .map(|ip| SocketAddr::new(ip, address.port))
+        .collect(),
+    )
+  }
+}
+
+impl<R: Runtime, P: ConnectionProvider> DnsResolver<R, P> {
+  /// Creates a resolver which sends the DNS queries through the given connection provider,
+  /// e.g. a mock transport for offline tests.
+  ///
+  /// [`AddressResolver::new`] uses the [`Default`] provider, so the resolver only
+  /// implements [`AddressResolver`] if `P` implements [`Default`].
+  pub fn with_provider(opts: DnsResolverOptions, provider: P) -> Self {
+    let attempts = opts
+      .dns
+      .as_ref()
+      .map_or(0, |dns| dns.resolver_opts.attempts + 1);
+    let address_family = opts.address_family;
+    let dns = opts.dns.map(|mut dns| {
+      if let Some(strategy) = address_family.strategy() {
+        dns.resolver_opts.ip_strategy = strategy;
+      }
+      AsyncResolver::new(dns.resolver_config, dns.resolver_opts, provider)
+    });
+    Self {
       dns,
-      record_ttl: opts.record_ttl,
+      lookup_opts: LookupOptions {
+        record_ttl: opts.record_ttl,
+        min_ttl: opts.min_ttl,
+        max_ttl: opts.max_ttl,
+        local_fallback: opts.local_fallback,
+        attempts,
+        address_family,
+      },
+      selection_policy: opts.selection_policy,
       cache: Default::default(),
-    })
+      negative_ttl: opts.negative_ttl,
+      negative_cache: Default::default(),
+      resolve_timeout: opts.resolve_timeout,
+      observer: opts.observer,
+      inflight: Default::default(),
+      _marker: std::marker::PhantomData,
+      #[cfg(test)]
+      lookups: Default::default(),
+    }
   }
 
-  async fn resolve(&self, address: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
-    match &address.kind {
-      Kind::Ip(ip) => Ok(SocketAddr::new(*ip, address.port)),
-      Kind::Domain(name) => {
-        // First, check cache
-        if let Some(ent) = self.cache.get(name.as_str()) {
-          let val = ent.value();
-          if !val.is_expired() {
-            return Ok(val.val);
-          } else {
-            ent.remove();
-          }
-        }
-
-        // Second, TCP lookup ip address
-        if let Some(ref dns) = self.dns {
-          if let Some(ip) = dns
-            .lookup_ip(name.fqdn_str())
-            .await
-            .map_err(|e| ResolveError::from(ResolveErrorKind::from(e)))?
-            .into_iter()
-            .next()
-          {
-            let addr = SocketAddr::new(ip, address.port);
-            self
-              .cache
-              .insert(name.clone(), CachedSocketAddr::new(addr, self.record_ttl));
-            return Ok(addr);
-          }
-        }
+  /// Joins the in-flight lookup of this domain, or starts a new one, unless
+  /// the domain has failed to resolve recently.
+  async fn join(&self, name: &Domain, port: u16) -> Result<IpAddrs, Error> {
+    self.observer.cache_miss(name);
+    let snapshot = self.inflight.snapshot();
+    if let Some(ent) = self.negative_cache.get(name.as_str()) {
+      let val = ent.value();
+      if !val.is_expired() {
+        self.observer.resolve_error(name);
+        return Err(Error::Resolve(val.err.clone()));
+      } else {
+        ent.remove();
+      }
+    }
 
-        // Finally, try to find the socket addr locally
-        let port = address.port;
-        let tsafe = name.clone();
+    let res = self
+      .inflight
+      .join(name, |snapshot| {
+        #[cfg(test)]
+        self
+          .lookups
+          .fetch_add(1, core::sync::atomic::Ordering::Relaxed);
 
-        let res = ToSocketAddrs::<R>::to_socket_addrs(&(tsafe.as_str(), port)).await?;
+        let lookup = lookup::<R, P>(
+          self.dns.clone(),
+          self.cache.clone(),
+          snapshot,
+          name.clone(),
+          port,
+          self.lookup_opts,
+        );
+        let name = name.clone();
+        with_timeout::<R, _, _>(self.resolve_timeout, lookup, || Error::Timeout(name))
+      })
+      .await
+      .map_err(Error::from_shared);
 
-        if let Some(addr) = res.into_iter().next() {
+    if res.is_err() {
+      self.observer.resolve_error(name);
+    }
+    if let Err(Error::Resolve(e)) = &res {
+      if !self.negative_ttl.is_zero() && e.is_not_found() {
+        snapshot.store(|| {
           self
-            .cache
-            .insert(name.clone(), CachedSocketAddr::new(addr, self.record_ttl));
-          return Ok(addr);
-        }
+            .negative_cache
+            .insert(name.clone(), CachedError::new(e.clone(), self.negative_ttl));
+        });
+      }
+    }
+    res
+  }
 
-        Err(Error::Resolve(ResolveError(ResolveErrorKind::NotFound(
-          name.clone(),
-        ))))
+  /// Looks up the SRV records of `name`, e.g. `_service._proto.example.com`, and returns
+  /// their targets and ports with their priorities and weights.
+  ///
+  /// The records are sorted by increasing priority, then by decreasing weight within a
+  /// priority. The SRV records are not cached, and the targets are not resolved,
+  /// resolve the returned [`HostAddr`]s to get their addresses.
+  ///
+  /// Returns a not found error if the DNS is disabled, or if the service is explicitly
+  /// not available, i.e. its only target is `.`.
+  pub async fn resolve_srv(&self, name: &Domain) -> Result<Vec<(HostAddr, u16, u16)>, Error> {
+    let not_found = || Error::Resolve(ResolveError(ResolveErrorKind::NotFound(name.clone())));
+    let Some(dns) = &self.dns else {
+      return Err(not_found());
+    };
+
+    let lookup = with_timeout::<R, _, _>(
+      self.resolve_timeout,
+      async {
+        dns
+          .srv_lookup(name.fqdn_str())
+          .await
+          .map_err(|e| Error::Resolve(ResolveError::from(ResolveErrorKind::from(e))))
+      },
+      || Error::Timeout(name.clone()),
+    )
+    .await?;
+
+    let mut records = lookup
+      .iter()
+      .filter(|srv| !srv.target().is_root())
+      .map(|srv| {
+        let target = srv.target().to_ascii();
+        Domain::try_from(target.as_str())
+          .map(|domain| {
+            (
+              HostAddr::from((domain, srv.port())),
+              srv.priority(),
+              srv.weight(),
+            )
+          })
+          .map_err(|e| Error::IO(io::Error::new(io::ErrorKind::InvalidData, e)))
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    if records.is_empty() {
+      return Err(not_found());
+    }
+    records.sort_by_key(|(_, priority, weight)| (*priority, core::cmp::Reverse(*weight)));
+    Ok(records)
+  }
+
+  /// Returns the address from the cache, without ever sending a query.
+  ///
+  /// Returns `None` if the domain is not cached, or its cached addresses have expired.
+  /// IP addresses are always returned.
+  pub fn resolve_cached(&self, address: &HostAddr) -> Option<SocketAddr> {
+    match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => Some(addr),
+      Err(name) => cached(&self.cache, name, |val| val.select(self.selection_policy))
+        .map(|ip| SocketAddr::new(ip, address.port)),
+    }
+  }
+
+  /// Returns a snapshot of the entries in the cache, including the expired ones
+  /// which have not been evicted yet.
+  pub fn cache_entries(&self) -> impl Iterator<Item = CacheEntry> + '_ {
+    self.cache.iter().map(|ent| ent.value().entry(ent.key()))
+  }
+
+  /// Removes the cached addresses (and the cached failure, if any) of the given domain,
+  /// so the next resolution of it does a fresh lookup.
+  ///
+  /// This is safe to call while the domain is being resolved concurrently: the lookup
+  /// in flight is not joined by the next resolutions, and its answer is not cached.
+  pub fn invalidate(&self, domain: &Domain) {
+    self.inflight.invalidate(domain, || {
+      self.cache.remove(domain.as_str());
+      self.negative_cache.remove(domain.as_str());
+    });
+  }
+
+  /// Removes all the cached addresses and failures.
+  ///
+  /// This is safe to call while domains are being resolved concurrently: the lookups
+  /// in flight are not joined by the next resolutions, and their answers are not cached.
+  pub fn clear_cache(&self) {
+    self.inflight.clear(|| {
+      self.cache.clear();
+      self.negative_cache.clear();
+    });
+  }
+}
+
+/// The options of [`DnsResolver`] used by a lookup.
+#[derive(Clone, Copy)]
+struct LookupOptions {
+  record_ttl: Duration,
+  min_ttl: Duration,
+  max_ttl: Duration,
+  local_fallback: bool,
+  /// The number of queries sent for a domain before giving up.
+  attempts: usize,
+  address_family: AddressFamilyPreference,
+}
+
+impl LookupOptions {
+  /// Returns the ttl of the records which are valid until `valid_until`, clamped
+  /// into `[min_ttl, max_ttl]`.
+  fn ttl(&self, valid_until: Instant) -> Duration {
+    valid_until
+      .saturating_duration_since(Instant::now())
+      .max(self.min_ttl)
+      .min(self.max_ttl)
+  }
+}
+
+async fn lookup<R: Runtime, P: ConnectionProvider>(
+  dns: Option<AsyncResolver<P>>,
+  cache: Arc<SkipMap<Domain, CachedSocketAddr>>,
+  snapshot: Snapshot,
+  name: Domain,
+  port: u16,
+  opts: LookupOptions,
+) -> Result<IpAddrs, Error> {
+  // TCP lookup ip address
+  if let Some(ref dns) = dns {
+    let lookup = match dns.lookup_ip(name.fqdn_str()).await {
+      Ok(lookup) => lookup,
+      Err(e) if matches!(e.kind(), hickory_resolver::error::ResolveErrorKind::Timeout) => {
+        return Err(Error::AllAttemptsFailed {
+          domain: name,
+          attempts: opts.attempts,
+        });
       }
+      Err(e) => return Err(ResolveError::from(ResolveErrorKind::from(e)).into()),
+    };
+    let mut ips = lookup.iter().collect::<IpAddrs>();
+    opts.address_family.apply(&mut ips, |ip| *ip);
+
+    if !ips.is_empty() {
+      // the lookup is valid until the minimum ttl of its records expires
+      let ttl = opts.ttl(lookup.valid_until());
+      let addrs = ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect();
+      snapshot.store(|| {
+        cache.insert(name, CachedSocketAddr::new(addrs, ttl));
+      });
+      return Ok(ips);
     }
   }
+
+  if !opts.local_fallback {
+    return Err(Error::Resolve(ResolveError(ResolveErrorKind::NotFound(
+      name,
+    ))));
+  }
+
+  // Finally, try to find the socket addr locally
+  let mut addrs = ToSocketAddrs::<R>::to_socket_addrs(&(name.as_str(), port))
+    .await?
+    .collect::<SmallVec<_>>();
+  opts.address_family.apply(&mut addrs, SocketAddr::ip);
+
+  if !addrs.is_empty() {
+    let cached = CachedSocketAddr::new(addrs, opts.record_ttl);
+    let ips = cached.ips();
+    snapshot.store(|| {
+      cache.insert(name, cached);
+    });
+    return Ok(ips);
+  }
+
+  Err(Error::Resolve(ResolveError(ResolveErrorKind::NotFound(
+    name,
+  ))))
 }
 
 #[cfg(test)]
@@ -297,7 +909,10 @@ mod tests {
     use agnostic::tokio::TokioRuntime;
 
     let resolver = DnsResolver::<TokioRuntime>::new(
-      DnsResolverOptions::default().with_record_ttl(Duration::from_millis(100)),
+      DnsResolverOptions::default()
+        .with_record_ttl(Duration::from_millis(100))
+        .with_min_ttl(Duration::ZERO)
+        .with_max_ttl(Duration::from_millis(100)),
     )
     .await
     .unwrap();
@@ -361,6 +976,449 @@ mod tests {
     assert!(resolver.resolve(&bad_addr).await.is_err());
   }
 
+  #[tokio::test]
+  async fn test_drop_waiter() {
+    use agnostic::tokio::TokioRuntime;
+    use futures::FutureExt;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    let other = addr.clone().with_port(9090);
+    let name = Domain::try_from("localhost").unwrap();
+
+    let mut dropped = Box::pin(resolver.resolve(&addr));
+    assert!((&mut dropped).now_or_never().is_none());
+    assert!(resolver.inflight.contains(&name));
+
+    let waiters = futures::future::join3(
+      resolver.resolve(&addr),
+      resolver.resolve(&addr),
+      resolver.resolve(&other),
+    );
+    drop(dropped);
+    let (a, b, c) = waiters.await;
+    let (a, b, c) = (a.unwrap(), b.unwrap(), c.unwrap());
+    assert_eq!(a, b);
+    assert_eq!(a.ip(), c.ip());
+    assert_eq!(c.port(), 9090);
+
+    assert!(!resolver.inflight.contains(&name));
+    assert!(!resolver
+      .cache
+      .get(name.as_str())
+      .unwrap()
+      .value()
+      .is_expired());
+  }
+
+  #[tokio::test]
+  async fn test_last_access() {
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    resolver.resolve(&addr).await.unwrap();
+
+    let ent = resolver.cache_entries().next().unwrap();
+    assert_eq!(ent.domain().as_str(), "localhost");
+    assert_eq!(ent.last_access(), ent.born());
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    resolver.resolve(&addr).await.unwrap();
+    let hit = resolver.cache_entries().next().unwrap();
+    assert_eq!(hit.born(), ent.born());
+    assert!(hit.last_access() >= ent.born() + Duration::from_millis(10));
+  }
+
+  #[tokio::test]
+  async fn test_resolve_all() {
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    let addrs = resolver.resolve_all(&addr).await.unwrap();
+    assert!(!addrs.is_empty());
+    assert!(addrs
+      .iter()
+      .all(|a| a.ip().is_loopback() && a.port() == 8080));
+    assert_eq!(resolver.resolve(&addr).await.unwrap(), addrs[0]);
+
+    // a domain with multiple records keeps all of them, which expire together
+    let name = Domain::try_from("multi.nodecraft.test").unwrap();
+    let records: SmallVec<[SocketAddr; 4]> = ["10.0.0.1:1", "10.0.0.2:1", "[fd00::1]:1"]
+      .into_iter()
+      .map(|s| s.parse().unwrap())
+      .collect();
+    resolver.cache.insert(
+      name.clone(),
+      CachedSocketAddr::new(records.clone(), Duration::from_millis(100)),
+    );
+
+    let addr = HostAddr::from((name.clone(), 9090));
+    let addrs = resolver.resolve_all(&addr).await.unwrap();
+    assert_eq!(
+      addrs.iter().map(|a| a.ip()).collect::<Vec<_>>(),
+      records.iter().map(|a| a.ip()).collect::<Vec<_>>()
+    );
+    assert!(addrs.iter().all(|a| a.port() == 9090));
+    assert_eq!(resolver.resolve(&addr).await.unwrap(), addrs[0]);
+    assert_eq!(resolver.cache_entries().count(), 2);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(resolver
+      .cache
+      .get(name.as_str())
+      .unwrap()
+      .value()
+      .is_expired());
+
+    let addr = HostAddr::try_from("[fe80::1%3]:80").unwrap();
+    assert_eq!(
+      resolver.resolve_all(&addr).await.unwrap().as_slice(),
+      ["[fe80::1%3]:80".parse::<SocketAddr>().unwrap()]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_without_local_fallback() {
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(
+      DnsResolverOptions::default()
+        .with_dns(None)
+        .with_local_fallback(false),
+    )
+    .await
+    .unwrap();
+
+    // `localhost` can only be resolved by `ToSocketAddrs` without DNS
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    match resolver.resolve(&addr).await {
+      Err(Error::Resolve(ResolveError(ResolveErrorKind::NotFound(name)))) => {
+        assert_eq!(name.as_str(), "localhost")
+      }
+      res => panic!("expected NotFound, got {res:?}"),
+    }
+    assert_eq!(resolver.cache_entries().count(), 0);
+
+    let addr = HostAddr::try_from("127.0.0.1:8080").unwrap();
+    assert!(resolver.resolve(&addr).await.is_ok());
+  }
+
+  #[tokio::test]
+  async fn test_round_robin() {
+    use agnostic::tokio::TokioRuntime;
+
+    let records: SmallVec<[SocketAddr; 4]> = ["10.0.0.1:1", "10.0.0.2:1", "10.0.0.3:1"]
+      .into_iter()
+      .map(|s| s.parse().unwrap())
+      .collect();
+    let name = Domain::try_from("multi.nodecraft.test").unwrap();
+    let addr = HostAddr::from((name.clone(), 9090));
+
+    let resolver = DnsResolver::<TokioRuntime>::new(
+      DnsResolverOptions::default()
+        .with_dns(None)
+        .with_selection_policy(SelectionPolicy::RoundRobin),
+    )
+    .await
+    .unwrap();
+    resolver.cache.insert(
+      name.clone(),
+      CachedSocketAddr::new(records.clone(), Duration::from_secs(60)),
+    );
+
+    let mut resolved = Vec::new();
+    for _ in 0..6 {
+      resolved.push(resolver.resolve(&addr).await.unwrap());
+    }
+    assert!(resolved.iter().all(|a| a.port() == 9090));
+    assert_eq!(
+      resolved.iter().map(|a| a.ip()).collect::<Vec<_>>(),
+      [1, 2, 0, 1, 2, 0].map(|i| records[i].ip())
+    );
+
+    // the default policy always returns the first address
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    resolver.cache.insert(
+      name.clone(),
+      CachedSocketAddr::new(records.clone(), Duration::from_secs(60)),
+    );
+    for _ in 0..3 {
+      assert_eq!(resolver.resolve(&addr).await.unwrap().ip(), records[0].ip());
+    }
+  }
+
+  #[tokio::test]
+  async fn test_negative_cache() {
+    use agnostic::tokio::TokioRuntime;
+    use core::sync::atomic::Ordering;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(
+      DnsResolverOptions::default()
+        .with_dns(None)
+        .with_local_fallback(false)
+        .with_negative_ttl(Duration::from_millis(100)),
+    )
+    .await
+    .unwrap();
+
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    for _ in 0..3 {
+      assert!(matches!(
+        resolver.resolve(&addr).await,
+        Err(Error::Resolve(ResolveError(ResolveErrorKind::NotFound(_))))
+      ));
+      assert!(resolver.resolve_all(&addr).await.is_err());
+    }
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(resolver.resolve(&addr).await.is_err());
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 2);
+
+    // negative caching can be disabled
+    let resolver = DnsResolver::<TokioRuntime>::new(
+      DnsResolverOptions::default()
+        .with_dns(None)
+        .with_local_fallback(false)
+        .with_negative_ttl(Duration::ZERO),
+    )
+    .await
+    .unwrap();
+    for _ in 0..3 {
+      assert!(resolver.resolve(&addr).await.is_err());
+    }
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 3);
+    assert!(resolver.negative_cache.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_invalidate() {
+    use agnostic::tokio::TokioRuntime;
+    use core::sync::atomic::Ordering;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    let localhost = Domain::try_from("localhost").unwrap();
+
+    resolver.resolve(&addr).await.unwrap();
+    resolver.resolve(&addr).await.unwrap();
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 1);
+
+    resolver.invalidate(&localhost);
+    assert_eq!(resolver.cache_entries().count(), 0);
+    resolver.resolve(&addr).await.unwrap();
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 2);
+    assert_eq!(resolver.cache_entries().count(), 1);
+
+    resolver.clear_cache();
+    assert_eq!(resolver.cache_entries().count(), 0);
+    resolver.resolve(&addr).await.unwrap();
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 3);
+
+    // the cached failures are dropped as well
+    let resolver = DnsResolver::<TokioRuntime>::new(
+      DnsResolverOptions::default()
+        .with_dns(None)
+        .with_local_fallback(false),
+    )
+    .await
+    .unwrap();
+    assert!(resolver.resolve(&addr).await.is_err());
+    assert!(!resolver.negative_cache.is_empty());
+    resolver.invalidate(&localhost);
+    assert!(resolver.negative_cache.is_empty());
+    assert!(resolver.resolve(&addr).await.is_err());
+    resolver.clear_cache();
+    assert!(resolver.negative_cache.is_empty());
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 2);
+  }
+
+  #[tokio::test]
+  async fn test_invalidate_inflight() {
+    use agnostic::tokio::TokioRuntime;
+    use core::sync::atomic::Ordering;
+    use futures::FutureExt;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    let localhost = Domain::try_from("localhost").unwrap();
+
+    let mut stale = Box::pin(resolver.resolve(&addr));
+    assert!((&mut stale).now_or_never().is_none());
+    resolver.invalidate(&localhost);
+    assert!(!resolver.inflight.contains(&localhost));
+
+    // a resolution after the invalidation does not join the stale lookup
+    let (stale, fresh) = futures::future::join(stale, resolver.resolve(&addr)).await;
+    stale.unwrap();
+    fresh.unwrap();
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 2);
+    assert_eq!(resolver.cache_entries().count(), 1);
+  }
+
+  #[tokio::test]
+  async fn test_resolve_timeout() {
+    use agnostic::tokio::TokioRuntime;
+    use hickory_resolver::config::NameServerConfigGroup;
+
+    // a black-holed name server, which never answers
+    let black_hole = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let config = ResolverConfig::from_parts(
+      None,
+      vec![],
+      NameServerConfigGroup::from_ips_clear(
+        &["127.0.0.1".parse().unwrap()],
+        black_hole.local_addr().unwrap().port(),
+        true,
+      ),
+    );
+    let mut opts = ResolverOpts::default();
+    opts.timeout = Duration::from_secs(30);
+    opts.attempts = 1;
+    let timeout = Duration::from_millis(200);
+    let resolver = DnsResolver::<TokioRuntime>::new(
+      DnsResolverOptions::default()
+        .with_dns(Some(
+          DnsOptions::new()
+            .with_resolver_config(config)
+            .with_resolver_opts(opts),
+        ))
+        .with_local_fallback(false)
+        .with_resolve_timeout(Some(timeout)),
+    )
+    .await
+    .unwrap();
+
+    let addr = HostAddr::try_from("www.example.com:80").unwrap();
+    let start = Instant::now();
+    match resolver.resolve(&addr).await {
+      Err(Error::Timeout(name)) => assert_eq!(name.as_str(), "www.example.com"),
+      res => panic!("expected Timeout, got {res:?}"),
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= timeout && elapsed < timeout * 5, "{elapsed:?}");
+    // timeouts are not negatively cached
+    assert!(resolver.negative_cache.is_empty());
+
+    // the timeout does not apply to cache hits
+    let name = Domain::try_from("www.example.com").unwrap();
+    let records: SmallVec<[SocketAddr; 4]> = smallvec::smallvec!["10.0.0.1:1".parse().unwrap()];
+    resolver.cache.insert(
+      name,
+      CachedSocketAddr::new(records, Duration::from_secs(60)),
+    );
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.1:80".parse::<SocketAddr>().unwrap()
+    );
+  }
+
+  #[tokio::test]
+  async fn test_resolve_cached() {
+    use agnostic::tokio::TokioRuntime;
+    use core::sync::atomic::Ordering;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+
+    // cold
+    assert_eq!(resolver.resolve_cached(&addr), None);
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 0);
+
+    // warm
+    let resolved = resolver.resolve(&addr).await.unwrap();
+    assert_eq!(resolver.resolve_cached(&addr), Some(resolved));
+    assert_eq!(
+      resolver.resolve_cached(&addr.clone().with_port(9090)),
+      Some(SocketAddr::new(resolved.ip(), 9090))
+    );
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 1);
+
+    // expired
+    let name = Domain::try_from("expired.nodecraft.test").unwrap();
+    resolver.cache.insert(
+      name.clone(),
+      CachedSocketAddr::new(
+        smallvec::smallvec!["10.0.0.1:1".parse().unwrap()],
+        Duration::ZERO,
+      ),
+    );
+    assert_eq!(resolver.resolve_cached(&HostAddr::from((name, 80))), None);
+
+    let addr = HostAddr::try_from("127.0.0.1:80").unwrap();
+    assert_eq!(
+      resolver.resolve_cached(&addr),
+      Some("127.0.0.1:80".parse().unwrap())
+    );
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 1);
+  }
+
+  #[tokio::test]
+  async fn test_ip_literal_domain() {
+    use agnostic::tokio::TokioRuntime;
+    use core::sync::atomic::Ordering;
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default())
+      .await
+      .unwrap();
+
+    // never produced by the parser, but may come from data decoded without validation
+    for (ip, expected) in [("127.0.0.1", "127.0.0.1:8080"), ("::1", "[::1]:8080")] {
+      let addr = HostAddr::from((Domain::from_ascii_unchecked(ip), 8080));
+      assert_eq!(addr.domain(), Some(ip));
+
+      let expected = expected.parse::<SocketAddr>().unwrap();
+      assert_eq!(resolver.resolve(&addr).await.unwrap(), expected);
+      assert_eq!(
+        resolver.resolve_all(&addr).await.unwrap().as_slice(),
+        [expected]
+      );
+    }
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 0);
+    assert_eq!(resolver.cache_entries().count(), 0);
+  }
+
+  #[test]
+  fn test_record_ttl() {
+    let opts = LookupOptions {
+      record_ttl: Duration::from_secs(60),
+      min_ttl: Duration::from_secs(5),
+      max_ttl: Duration::from_secs(300),
+      local_fallback: true,
+      attempts: 1,
+      address_family: AddressFamilyPreference::Any,
+    };
+
+    let ttl = opts.ttl(Instant::now() + Duration::from_secs(120));
+    assert!(ttl > Duration::from_secs(119) && ttl <= Duration::from_secs(120));
+    assert_eq!(
+      opts.ttl(Instant::now() + Duration::from_secs(1)),
+      Duration::from_secs(5)
+    );
+    assert_eq!(opts.ttl(Instant::now()), Duration::from_secs(5));
+    assert_eq!(
+      opts.ttl(Instant::now() + Duration::from_secs(3600)),
+      Duration::from_secs(300)
+    );
+  }
+
   #[test]
   fn test_opts() {
     let opts = DnsOptions::new();
@@ -376,5 +1434,369 @@ mod tests {
     opts.set_dns(Some(Default::default()));
     opts.set_record_ttl(Duration::from_secs(100));
     opts.record_ttl();
+    assert_eq!(opts.min_ttl(), Duration::from_secs(1));
+    assert_eq!(opts.max_ttl(), Duration::from_secs(86400));
+    opts
+      .set_min_ttl(Duration::ZERO)
+      .set_max_ttl(Duration::from_secs(60));
+    assert_eq!(opts.min_ttl(), Duration::ZERO);
+    assert_eq!(opts.max_ttl(), Duration::from_secs(60));
+    assert_eq!(opts.resolve_timeout(), None);
+    opts.set_resolve_timeout(Some(Duration::from_secs(3)));
+    assert_eq!(opts.resolve_timeout(), Some(Duration::from_secs(3)));
+    assert!(opts.local_fallback());
+    opts.set_local_fallback(false);
+    assert!(!opts.local_fallback());
+    assert_eq!(opts.selection_policy(), SelectionPolicy::First);
+    opts.set_selection_policy(SelectionPolicy::RoundRobin);
+    assert_eq!(opts.negative_ttl(), Duration::from_secs(5));
+    opts.set_negative_ttl(Duration::ZERO);
+    assert_eq!(opts.negative_ttl(), Duration::ZERO);
+    assert_eq!(opts.selection_policy(), SelectionPolicy::RoundRobin);
+    assert_eq!(opts.address_family(), AddressFamilyPreference::Any);
+    opts.set_address_family(AddressFamilyPreference::PreferV4);
+    assert_eq!(opts.address_family(), AddressFamilyPreference::PreferV4);
+  }
+
+  mod mock {
+    use hickory_resolver::{
+      config::NameServerConfig,
+      error::{ResolveError, ResolveErrorKind},
+      proto::{
+        op::{Message, MessageType},
+        rr::{
+          rdata::{A, AAAA, SRV},
+          Name, RData, Record, RecordType,
+        },
+        xfer::{DnsHandle, DnsRequest, DnsResponse},
+      },
+    };
+
+    use super::*;
+
+    type Net = <agnostic::tokio::TokioRuntime as Runtime>::Net;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    pub(super) const ADDR: std::net::Ipv4Addr = std::net::Ipv4Addr::new(10, 1, 2, 3);
+
+    pub(super) const ADDR_V6: std::net::Ipv6Addr =
+      std::net::Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1);
+
+    /// The priority, weight, port and target of the `SRV` records.
+    pub(super) const SRV_RECORDS: [(u16, u16, u16, &str); 4] = [
+      (20, 0, 7000, "backup.nodecraft.test."),
+      (10, 10, 6000, "light.nodecraft.test."),
+      (10, 60, 6001, "heavy.nodecraft.test."),
+      (30, 0, 0, "."),
+    ];
+
+    /// A connection provider which answers the `A` queries with [`ADDR`], and the `AAAA`
+    /// queries for the names starting with `dual.` or `v6.` with [`ADDR_V6`], without
+    /// sending anything over the network. The names starting with `v6.` have no `A` record.
+    ///
+    /// The `SRV` queries are answered with the records of [`SRV_RECORDS`].
+    #[derive(Clone, Default)]
+    pub(super) struct MockProvider;
+
+    #[derive(Clone)]
+    pub(super) struct MockConn;
+
+    impl DnsHandle for MockConn {
+      type Response = futures::stream::Iter<std::vec::IntoIter<Result<DnsResponse, ResolveError>>>;
+      type Error = ResolveError;
+
+      fn send<Req: Into<DnsRequest> + Unpin + Send + 'static>(
+        &self,
+        request: Req,
+      ) -> Self::Response {
+        let request = request.into();
+        let mut msg = Message::new();
+        msg
+          .set_id(request.id())
+          .set_message_type(MessageType::Response);
+        for query in request.queries() {
+          msg.add_query(query.clone());
+          let name = query.name().to_ascii();
+          if query.query_type() == RecordType::A && !name.starts_with("v6.") {
+            msg.add_answer(Record::from_rdata(
+              query.name().clone(),
+              60,
+              RData::A(A(ADDR)),
+            ));
+          }
+          if query.query_type() == RecordType::AAAA
+            && (name.starts_with("dual.") || name.starts_with("v6."))
+          {
+            msg.add_answer(Record::from_rdata(
+              query.name().clone(),
+              60,
+              RData::AAAA(AAAA(ADDR_V6)),
+            ));
+          }
+          if query.query_type() == RecordType::SRV {
+            for (priority, weight, port, target) in SRV_RECORDS {
+              let target = Name::from_ascii(target).unwrap();
+              msg.add_answer(Record::from_rdata(
+                query.name().clone(),
+                60,
+                RData::SRV(SRV::new(priority, weight, port, target)),
+              ));
+            }
+          }
+        }
+        futures::stream::iter(vec![DnsResponse::from_message(msg).map_err(Into::into)])
+      }
+    }
+
+    /// A connection provider whose name servers always time out, and which counts
+    /// the queries sent to them.
+    #[derive(Clone, Default)]
+    pub(super) struct TimeoutProvider(pub(super) Arc<AtomicUsize>);
+
+    impl DnsHandle for TimeoutProvider {
+      type Response = futures::stream::Iter<std::vec::IntoIter<Result<DnsResponse, ResolveError>>>;
+      type Error = ResolveError;
+
+      fn send<Req: Into<DnsRequest> + Unpin + Send + 'static>(&self, _: Req) -> Self::Response {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        futures::stream::iter(vec![Err(ResolveErrorKind::Timeout.into())])
+      }
+    }
+
+    impl ConnectionProvider for TimeoutProvider {
+      type Conn = Self;
+      type FutureConn = futures::future::Ready<Result<Self, ResolveError>>;
+      type RuntimeProvider = agnostic::dns::AsyncRuntimeProvider<Net>;
+
+      fn new_connection(&self, _: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+        futures::future::ready(Ok(self.clone()))
+      }
+    }
+
+    impl ConnectionProvider for MockProvider {
+      type Conn = MockConn;
+      type FutureConn = futures::future::Ready<Result<MockConn, ResolveError>>;
+      type RuntimeProvider = agnostic::dns::AsyncRuntimeProvider<Net>;
+
+      fn new_connection(&self, _: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+        futures::future::ready(Ok(MockConn))
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn test_with_provider() {
+    use agnostic::tokio::TokioRuntime;
+    use core::sync::atomic::Ordering;
+
+    let resolver = DnsResolver::<TokioRuntime, _>::with_provider(
+      DnsResolverOptions::default().with_local_fallback(false),
+      mock::MockProvider,
+    );
+    let addr = HostAddr::try_from("mock.nodecraft.test:8080").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.1.2.3:8080".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+      resolver
+        .resolve_all(&addr.clone().with_port(80))
+        .await
+        .unwrap()
+        .as_slice(),
+      ["10.1.2.3:80".parse::<SocketAddr>().unwrap()]
+    );
+    assert_eq!(resolver.lookups.load(Ordering::Relaxed), 1);
+  }
+
+  #[tokio::test]
+  async fn test_all_attempts_failed() {
+    use agnostic::{
+      dns::{LookupIpStrategy, NameServerConfig, Protocol},
+      tokio::TokioRuntime,
+    };
+    use core::sync::atomic::Ordering;
+
+    let config = ResolverConfig::from_parts(
+      None,
+      vec![],
+      vec![NameServerConfig::new(
+        "10.0.0.53:53".parse().unwrap(),
+        Protocol::Udp,
+      )],
+    );
+    let mut opts = ResolverOpts::default();
+    opts.attempts = 2;
+    opts.ip_strategy = LookupIpStrategy::Ipv4Only;
+    let provider = mock::TimeoutProvider::default();
+    let resolver = DnsResolver::<TokioRuntime, _>::with_provider(
+      DnsResolverOptions::default().with_dns(Some(
+        DnsOptions::new()
+          .with_resolver_config(config)
+          .with_resolver_opts(opts),
+      )),
+      provider.clone(),
+    );
+
+    let addr = HostAddr::try_from("www.example.com:80").unwrap();
+    match resolver.resolve(&addr).await {
+      Err(Error::AllAttemptsFailed { domain, attempts }) => {
+        assert_eq!(domain.as_str(), "www.example.com");
+        assert_eq!(attempts, 3);
+      }
+      res => panic!("expected AllAttemptsFailed, got {res:?}"),
+    }
+    // the first query and 2 retries
+    assert_eq!(provider.0.load(Ordering::SeqCst), 3);
+    // exhausted attempts are not negatively cached
+    assert!(resolver.negative_cache.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_observer() {
+    use agnostic::tokio::TokioRuntime;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<&'static str>>);
+
+    impl ResolverObserver for Recorder {
+      fn on_cache_hit(&self, _: &Domain) {
+        self.0.lock().unwrap().push("hit");
+      }
+
+      fn on_cache_miss(&self, _: &Domain) {
+        self.0.lock().unwrap().push("miss");
+      }
+
+      fn on_resolve_error(&self, _: &Domain) {
+        self.0.lock().unwrap().push("error");
+      }
+    }
+
+    let recorder = Arc::new(Recorder::default());
+    let opts = DnsResolverOptions::default()
+      .with_local_fallback(false)
+      .with_observer(Some(recorder.clone()));
+    assert!(opts.observer().is_some());
+    let resolver = DnsResolver::<TokioRuntime, _>::with_provider(opts, mock::MockProvider);
+
+    let addr = HostAddr::try_from("mock.nodecraft.test:8080").unwrap();
+    resolver.resolve(&addr).await.unwrap();
+    resolver.resolve(&addr).await.unwrap();
+    // literal addresses are neither hits nor misses
+    resolver
+      .resolve(&HostAddr::try_from("127.0.0.1:8080").unwrap())
+      .await
+      .unwrap();
+    assert_eq!(*recorder.0.lock().unwrap(), ["miss", "hit"]);
+  }
+
+  #[tokio::test]
+  async fn test_address_family() {
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = |family| {
+      DnsResolver::<TokioRuntime, _>::with_provider(
+        DnsResolverOptions::default()
+          .with_local_fallback(false)
+          .with_address_family(family),
+        mock::MockProvider,
+      )
+    };
+    let dual = HostAddr::try_from("dual.nodecraft.test:8080").unwrap();
+    let v4 = HostAddr::try_from("mock.nodecraft.test:8080").unwrap();
+    let a = SocketAddr::from((mock::ADDR, 8080));
+    let aaaa = SocketAddr::from((mock::ADDR_V6, 8080));
+
+    let prefer_v6 = resolver(AddressFamilyPreference::PreferV6);
+    assert_eq!(prefer_v6.resolve(&dual).await.unwrap(), aaaa);
+    assert_eq!(
+      prefer_v6.resolve_all(&dual).await.unwrap().as_slice(),
+      [aaaa, a]
+    );
+    // falls back to the A record
+    assert_eq!(prefer_v6.resolve(&v4).await.unwrap(), a);
+
+    let prefer_v4 = resolver(AddressFamilyPreference::PreferV4);
+    assert_eq!(
+      prefer_v4.resolve_all(&dual).await.unwrap().as_slice(),
+      [a, aaaa]
+    );
+
+    let v6_only = resolver(AddressFamilyPreference::V6Only);
+    assert_eq!(v6_only.resolve_all(&dual).await.unwrap().as_slice(), [aaaa]);
+    assert!(matches!(v6_only.resolve(&v4).await, Err(Error::Resolve(e)) if e.is_not_found()));
+
+    let v4_only = resolver(AddressFamilyPreference::V4Only);
+    assert_eq!(v4_only.resolve_all(&dual).await.unwrap().as_slice(), [a]);
+    let v6 = HostAddr::try_from("v6.nodecraft.test:8080").unwrap();
+    assert!(matches!(v4_only.resolve(&v6).await, Err(Error::Resolve(e)) if e.is_not_found()));
+    assert_eq!(
+      resolver(AddressFamilyPreference::PreferV4)
+        .resolve(&v6)
+        .await
+        .unwrap(),
+      aaaa
+    );
+  }
+
+  #[test]
+  fn test_address_family_apply() {
+    let a = IpAddr::from([10, 0, 0, 1]);
+    let b = IpAddr::from([10, 0, 0, 2]);
+    let aaaa = IpAddr::from(std::net::Ipv6Addr::LOCALHOST);
+    let ips: IpAddrs = smallvec::smallvec![a, aaaa, b];
+    let apply = |family: AddressFamilyPreference| {
+      let mut ips = ips.clone();
+      family.apply(&mut ips, |ip| *ip);
+      ips
+    };
+
+    assert_eq!(apply(AddressFamilyPreference::Any).as_slice(), [a, aaaa, b]);
+    assert_eq!(
+      apply(AddressFamilyPreference::PreferV6).as_slice(),
+      [aaaa, a, b]
+    );
+    assert_eq!(
+      apply(AddressFamilyPreference::PreferV4).as_slice(),
+      [a, b, aaaa]
+    );
+    assert_eq!(apply(AddressFamilyPreference::V4Only).as_slice(), [a, b]);
+    assert_eq!(apply(AddressFamilyPreference::V6Only).as_slice(), [aaaa]);
+  }
+
+  #[tokio::test]
+  async fn test_resolve_srv() {
+    use agnostic::tokio::TokioRuntime;
+
+    let resolver = DnsResolver::<TokioRuntime, _>::with_provider(
+      DnsResolverOptions::default().with_local_fallback(false),
+      mock::MockProvider,
+    );
+    let name = Domain::try_from("_raft._tcp.nodecraft.test").unwrap();
+    let records = resolver
+      .resolve_srv(&name)
+      .await
+      .unwrap()
+      .into_iter()
+      .map(|(addr, priority, weight)| (addr.to_string(), priority, weight))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      records,
+      [
+        ("heavy.nodecraft.test:6001".to_string(), 10, 60),
+        ("light.nodecraft.test:6000".to_string(), 10, 10),
+        ("backup.nodecraft.test:7000".to_string(), 20, 0),
+      ]
+    );
+
+    let resolver = DnsResolver::<TokioRuntime>::new(DnsResolverOptions::default().with_dns(None))
+      .await
+      .unwrap();
+    assert!(
+      matches!(resolver.resolve_srv(&name).await, Err(Error::Resolve(e)) if e.is_not_found())
+    );
   }
 }
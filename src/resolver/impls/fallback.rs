@@ -0,0 +1,204 @@
+use std::future::Future;
+
+use futures::future::join;
+use smallvec::SmallVec;
+
+use super::super::AddressResolver;
+
+/// The error returned by [`FallbackResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum FallbackError<A, B> {
+  /// The primary resolver failed to be created.
+  #[error("primary resolver: {0}")]
+  Primary(A),
+  /// The fallback resolver failed to be created.
+  #[error("fallback resolver: {0}")]
+  Fallback(B),
+  /// Both resolvers failed.
+  #[error("primary resolver: {0}; fallback resolver: {1}")]
+  Both(A, B),
+}
+
+/// A resolver which asks the primary resolver first, and only asks the fallback resolver
+/// if the primary one fails.
+///
+/// Resolution only fails if both resolvers fail, with [`FallbackError::Both`]. Fallbacks
+/// can be nested to chain more resolvers, e.g. a static host map, then DNS, then the
+/// system resolver.
+///
+/// The [`Runtime`](AddressResolver::Runtime) of the resolver is the one of the
+/// primary resolver.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use agnostic::tokio::TokioRuntime;
+/// use nodecraft::{
+///   resolver::{address::HostAddrResolver, r#static::StaticResolver, AddressResolver, FallbackResolver},
+///   Domain, HostAddr,
+/// };
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let hosts = HashMap::from([(
+///   Domain::try_from("db.internal").unwrap(),
+///   vec!["10.0.0.1".parse().unwrap()],
+/// )]);
+/// let resolver = FallbackResolver::new(
+///   StaticResolver::<TokioRuntime>::from(hosts),
+///   HostAddrResolver::<TokioRuntime>::new(Default::default()),
+/// );
+/// let addr = HostAddr::try_from("db.internal:5432").unwrap();
+/// assert_eq!(resolver.resolve(&addr).await.unwrap().to_string(), "10.0.0.1:5432");
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct FallbackResolver<A, B> {
+  primary: A,
+  fallback: B,
+}
+
+impl<A, B> FallbackResolver<A, B> {
+  /// Creates a new resolver which asks `primary` first, then `fallback`.
+  #[inline]
+  pub const fn new(primary: A, fallback: B) -> Self {
+    Self { primary, fallback }
+  }
+
+  /// Returns the primary resolver.
+  #[inline]
+  pub const fn primary(&self) -> &A {
+    &self.primary
+  }
+
+  /// Returns the fallback resolver.
+  #[inline]
+  pub const fn fallback(&self) -> &B {
+    &self.fallback
+  }
+
+  /// Consumes the resolver and returns the primary and the fallback resolvers.
+  #[inline]
+  pub fn into_components(self) -> (A, B) {
+    (self.primary, self.fallback)
+  }
+}
+
+impl<A, B> AddressResolver for FallbackResolver<A, B>
+where
+  A: AddressResolver,
+  B: AddressResolver<Address = A::Address, ResolvedAddress = A::ResolvedAddress>,
+{
+  type Address = A::Address;
+  type ResolvedAddress = A::ResolvedAddress;
+  type Error = FallbackError<A::Error, B::Error>;
+  type Runtime = A::Runtime;
+  type Options = (A::Options, B::Options);
+
+  async fn new((primary, fallback): Self::Options) -> Result<Self, Self::Error>
+  where
+    Self: Sized,
+  {
+    match join(A::new(primary), B::new(fallback)).await {
+      (Ok(primary), Ok(fallback)) => Ok(Self { primary, fallback }),
+      (Err(e), Ok(_)) => Err(FallbackError::Primary(e)),
+      (Ok(_), Err(e)) => Err(FallbackError::Fallback(e)),
+      (Err(a), Err(b)) => Err(FallbackError::Both(a, b)),
+    }
+  }
+
+  fn resolve(
+    &self,
+    address: &Self::Address,
+  ) -> impl Future<Output = Result<Self::ResolvedAddress, Self::Error>> + Send {
+    // Create both lookups up front, so the returned future does not borrow `address`.
+    // The fallback lookup is only polled if the primary one fails.
+    let primary = self.primary.resolve(address);
+    let fallback = self.fallback.resolve(address);
+    async move {
+      match primary.await {
+        Ok(addr) => Ok(addr),
+        Err(a) => fallback.await.map_err(|b| FallbackError::Both(a, b)),
+      }
+    }
+  }
+
+  fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> impl Future<Output = Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error>> + Send {
+    let primary = self.primary.resolve_all(address);
+    let fallback = self.fallback.resolve_all(address);
+    async move {
+      match primary.await {
+        Ok(addrs) => Ok(addrs),
+        Err(a) => fallback.await.map_err(|b| FallbackError::Both(a, b)),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::HashMap, net::SocketAddr};
+
+  use agnostic::tokio::TokioRuntime;
+
+  use super::*;
+  use crate::{
+    resolver::{
+      address::HostAddrResolver,
+      r#static::{StaticResolveError, StaticResolver},
+    },
+    Domain, HostAddr,
+  };
+
+  #[tokio::test]
+  async fn test_fallback_resolver() {
+    let hosts = HashMap::from([(
+      Domain::try_from("db.internal").unwrap(),
+      vec!["10.0.0.1".parse().unwrap()],
+    )]);
+    let resolver = <FallbackResolver<StaticResolver<TokioRuntime>, HostAddrResolver<TokioRuntime>> as AddressResolver>::new((
+      hosts.clone(),
+      Default::default(),
+    ))
+    .await
+    .unwrap();
+
+    // the primary resolver hits
+    let addr = HostAddr::try_from("db.internal:5432").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.1:5432".parse::<SocketAddr>().unwrap()
+    );
+
+    // the static map misses, and the fallback resolves from the hosts file
+    let addr = HostAddr::try_from("localhost:8080").unwrap();
+    assert!(resolver.primary().resolve(&addr).await.is_err());
+    let resolved = resolver.resolve(&addr).await.unwrap();
+    assert!(resolved.ip().is_loopback());
+    assert_eq!(resolved.port(), 8080);
+    let all = resolver.resolve_all(&addr).await.unwrap();
+    assert!(all.contains(&resolved));
+
+    // both miss
+    let resolver = FallbackResolver::new(
+      StaticResolver::<TokioRuntime>::from(hosts.clone()),
+      StaticResolver::<TokioRuntime>::from(HashMap::new()),
+    );
+    let addr = HostAddr::try_from("www.example.com:80").unwrap();
+    assert!(matches!(
+      resolver.resolve(&addr).await.unwrap_err(),
+      FallbackError::Both(
+        StaticResolveError::NotFound(_),
+        StaticResolveError::NotFound(_)
+      )
+    ));
+    assert!(matches!(
+      resolver.resolve_all(&addr).await.unwrap_err(),
+      FallbackError::Both(_, _)
+    ));
+  }
+}
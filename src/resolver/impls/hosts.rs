@@ -0,0 +1,445 @@
+use std::{
+  borrow::Cow,
+  collections::HashMap,
+  io,
+  net::{IpAddr, SocketAddr},
+  path::{Path, PathBuf},
+  sync::{Arc, RwLock},
+  time::{Duration, Instant},
+};
+
+use agnostic::RuntimeLite;
+use smallvec::SmallVec;
+
+use super::super::AddressResolver;
+use crate::{address::Domain, HostAddr};
+
+/// The error returned by [`HostsFileResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum HostsFileError {
+  /// The hosts file could not be read.
+  #[error(transparent)]
+  Io(#[from] io::Error),
+  /// The domain is not in the hosts file.
+  #[error("no address found for {0}")]
+  NotFound(Domain),
+}
+
+fn default_path() -> PathBuf {
+  PathBuf::from(if cfg!(windows) {
+    r"C:\Windows\System32\drivers\etc\hosts"
+  } else {
+    "/etc/hosts"
+  })
+}
+
+/// The options used to construct a [`HostsFileResolver`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostsFileResolverOptions {
+  #[cfg_attr(feature = "serde", serde(default = "default_path"))]
+  path: PathBuf,
+  #[cfg_attr(feature = "serde", serde(with = "humantime_serde", default))]
+  reload_interval: Option<Duration>,
+}
+
+impl Default for HostsFileResolverOptions {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl HostsFileResolverOptions {
+  /// Create a new [`HostsFileResolverOptions`], which reads the hosts file of the system once.
+  #[inline]
+  pub fn new() -> Self {
+    Self {
+      path: default_path(),
+      reload_interval: None,
+    }
+  }
+
+  /// Set the path of the hosts file in builder pattern
+  #[inline]
+  pub fn with_path(mut self, val: impl Into<PathBuf>) -> Self {
+    self.path = val.into();
+    self
+  }
+
+  /// Set the path of the hosts file
+  #[inline]
+  pub fn set_path(&mut self, val: impl Into<PathBuf>) -> &mut Self {
+    self.path = val.into();
+    self
+  }
+
+  /// Returns the path of the hosts file. Default is `/etc/hosts`
+  /// (`C:\Windows\System32\drivers\etc\hosts` on Windows).
+  #[inline]
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Set how often the hosts file is read again in builder pattern, `None` to never reload it.
+  #[inline]
+  pub const fn with_reload_interval(mut self, val: Option<Duration>) -> Self {
+    self.reload_interval = val;
+    self
+  }
+
+  /// Set how often the hosts file is read again, `None` to never reload it.
+  #[inline]
+  pub fn set_reload_interval(&mut self, val: Option<Duration>) -> &mut Self {
+    self.reload_interval = val;
+    self
+  }
+
+  /// Returns how often the hosts file is read again. Default is `None`, i.e. never.
+  #[inline]
+  pub const fn reload_interval(&self) -> Option<Duration> {
+    self.reload_interval
+  }
+}
+
+/// The entries of a hosts file.
+pub type Hosts = HashMap<Domain, Vec<IpAddr>>;
+
+/// Parses the content of a hosts file, i.e. lines of an IP followed by its names.
+///
+/// Comments starting with `#` are ignored, and so are the lines whose IP or names are
+/// not valid. Names are lowercased, as they are case-insensitive. A name listed on
+/// several lines gets the addresses of all of them, in the order of the file.
+///
+/// # Example
+///
+/// ```
+/// use nodecraft::{resolver::hosts::parse_hosts, Domain};
+///
+/// let hosts = parse_hosts("127.0.0.1 localhost # the loopback\n::1 localhost ip6-localhost");
+/// let localhost = Domain::try_from("localhost").unwrap();
+/// assert_eq!(hosts[&localhost], ["127.0.0.1".parse::<std::net::IpAddr>().unwrap(), "::1".parse().unwrap()]);
+/// ```
+pub fn parse_hosts(content: &str) -> Hosts {
+  let mut hosts = Hosts::new();
+  for line in content.lines() {
+    let line = line.split_once('#').map_or(line, |(line, _)| line);
+    let mut fields = line.split_whitespace();
+    let Some(Ok(ip)) = fields.next().map(str::parse::<IpAddr>) else {
+      continue;
+    };
+
+    for name in fields {
+      if let Ok(name) = Domain::try_from(name.to_ascii_lowercase().as_str()) {
+        let ips = hosts.entry(name).or_default();
+        if !ips.contains(&ip) {
+          ips.push(ip);
+        }
+      }
+    }
+  }
+  hosts
+}
+
+struct Loaded {
+  hosts: Arc<Hosts>,
+  at: Instant,
+}
+
+/// A resolver which resolves domains from a hosts file, e.g. `/etc/hosts`, and never sends
+/// DNS queries.
+///
+/// The file is read when the resolver is created, and again on the first resolution after
+/// each [reload interval](HostsFileResolverOptions::with_reload_interval), if any. If
+/// reading it again fails, the previous entries are kept until the next interval.
+///
+/// The file is always read on a blocking thread of the runtime, and concurrent resolutions
+/// keep using the previous entries while it is being read again.
+///
+/// IP addresses are returned directly.
+///
+/// # Example
+///
+/// ```no_run
+/// use nodecraft::{
+///   resolver::{hosts::{HostsFileResolver, HostsFileResolverOptions}, AddressResolver},
+///   HostAddr,
+/// };
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let resolver = HostsFileResolver::<agnostic::tokio::TokioRuntime>::new(
+///   HostsFileResolverOptions::new().with_path("/etc/hosts"),
+/// )
+/// .await
+/// .unwrap();
+/// let addr = HostAddr::try_from("localhost:8080").unwrap();
+/// assert!(resolver.resolve(&addr).await.unwrap().ip().is_loopback());
+/// # });
+/// ```
+pub struct HostsFileResolver<R> {
+  path: PathBuf,
+  reload_interval: Option<Duration>,
+  loaded: RwLock<Loaded>,
+  _marker: std::marker::PhantomData<R>,
+}
+
+impl<R> HostsFileResolver<R> {
+  /// Returns the entries of the hosts file, as of the last time it was read.
+  pub fn hosts(&self) -> Arc<Hosts> {
+    self.loaded.read().unwrap().hosts.clone()
+  }
+
+  fn store(&self, hosts: Hosts) {
+    *self.loaded.write().unwrap() = Loaded {
+      hosts: Arc::new(hosts),
+      at: Instant::now(),
+    };
+  }
+}
+
+impl<R: RuntimeLite> HostsFileResolver<R> {
+  /// Reads the hosts file again.
+  pub async fn reload(&self) -> io::Result<()> {
+    let hosts = read_hosts::<R>(self.path.clone()).await?;
+    self.store(hosts);
+    Ok(())
+  }
+
+  /// Returns the current entries, reading the hosts file again if they are stale.
+  async fn current(&self) -> Arc<Hosts> {
+    let Some(interval) = self.reload_interval else {
+      return self.hosts();
+    };
+
+    {
+      let mut loaded = self.loaded.write().unwrap();
+      if loaded.at.elapsed() < interval {
+        return loaded.hosts.clone();
+      }
+      // claim the reload, so concurrent resolutions keep using the current entries
+      // instead of reading the file as well
+      loaded.at = Instant::now();
+    }
+
+    // the lock is not held while reading, and the previous entries are kept on failure
+    match read_hosts::<R>(self.path.clone()).await {
+      Ok(hosts) => {
+        let hosts = Arc::new(hosts);
+        self.loaded.write().unwrap().hosts = hosts.clone();
+        hosts
+      }
+      Err(_) => self.hosts(),
+    }
+  }
+
+  async fn lookup(&self, name: &Domain) -> Result<SmallVec<[IpAddr; 4]>, HostsFileError> {
+    let key = name.as_str();
+    let key = if key.bytes().any(|b| b.is_ascii_uppercase()) {
+      Cow::Owned(key.to_ascii_lowercase())
+    } else {
+      Cow::Borrowed(key)
+    };
+    match self.current().await.get(key.as_ref()) {
+      Some(ips) if !ips.is_empty() => Ok(ips.iter().copied().collect()),
+      _ => Err(HostsFileError::NotFound(name.clone())),
+    }
+  }
+}
+
+/// Reads and parses the hosts file on a blocking thread, off the executor.
+async fn read_hosts<R: RuntimeLite>(path: PathBuf) -> io::Result<Hosts> {
+  R::spawn_blocking(move || std::fs::read_to_string(path).map(|content| parse_hosts(&content)))
+    .await
+    .map_err(Into::into)?
+}
+
+impl<R: RuntimeLite> AddressResolver for HostsFileResolver<R> {
+  type Address = HostAddr;
+  type ResolvedAddress = SocketAddr;
+  type Error = HostsFileError;
+  type Runtime = R;
+  type Options = HostsFileResolverOptions;
+
+  async fn new(opts: Self::Options) -> Result<Self, Self::Error> {
+    let hosts = read_hosts::<R>(opts.path.clone()).await?;
+    Ok(Self {
+      path: opts.path,
+      reload_interval: opts.reload_interval,
+      loaded: RwLock::new(Loaded {
+        hosts: Arc::new(hosts),
+        at: Instant::now(),
+      }),
+      _marker: std::marker::PhantomData,
+    })
+  }
+
+  async fn resolve(&self, address: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
+    match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => Ok(addr),
+      Err(name) => self
+        .lookup(name)
+        .await
+        .map(|ips| SocketAddr::new(ips[0], address.port)),
+    }
+  }
+
+  async fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+    match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => Ok(smallvec::smallvec![addr]),
+      Err(name) => self.lookup(name).await.map(|ips| {
+        ips
+          .into_iter()
+          .map(|ip| SocketAddr::new(ip, address.port))
+          .collect()
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use agnostic::tokio::TokioRuntime;
+
+  use super::*;
+
+  struct TempFile(PathBuf);
+
+  impl TempFile {
+    fn new(name: &str, content: &str) -> Self {
+      let path = std::env::temp_dir().join(format!("nodecraft-{}-{name}", std::process::id()));
+      std::fs::write(&path, content).unwrap();
+      Self(path)
+    }
+  }
+
+  impl Drop for TempFile {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_file(&self.0);
+    }
+  }
+
+  #[test]
+  fn test_parse_hosts() {
+    let hosts = parse_hosts(
+      "# comment\n\
+       127.0.0.1\tlocalhost db.internal # trailing comment\n\
+       \n\
+       10.0.0.1 db.internal DB.internal\n\
+       fd00::1 cache.internal\n\
+       not-an-ip web.internal\n\
+       10.0.0.2 -bad.internal\n",
+    );
+
+    let db = Domain::try_from("db.internal").unwrap();
+    assert_eq!(
+      hosts[&db],
+      [
+        "127.0.0.1".parse::<IpAddr>().unwrap(),
+        "10.0.0.1".parse().unwrap()
+      ]
+    );
+    assert_eq!(
+      hosts[&Domain::try_from("cache.internal").unwrap()],
+      ["fd00::1".parse::<IpAddr>().unwrap()]
+    );
+    assert!(hosts.contains_key(&Domain::try_from("localhost").unwrap()));
+    assert!(!hosts.contains_key(&Domain::try_from("web.internal").unwrap()));
+    let mut keys = hosts.keys().map(Domain::as_str).collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, ["cache.internal", "db.internal", "localhost"]);
+  }
+
+  #[tokio::test]
+  async fn test_hosts_file_resolver() {
+    let file = TempFile::new(
+      "hosts",
+      "10.0.0.1 db.internal\n10.0.0.2 db.internal\nfd00::1 cache.internal # ipv6\n",
+    );
+    let resolver =
+      HostsFileResolver::<TokioRuntime>::new(HostsFileResolverOptions::new().with_path(&file.0))
+        .await
+        .unwrap();
+
+    let addr = HostAddr::try_from("db.internal:5432").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.1:5432".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+      resolver.resolve_all(&addr).await.unwrap().as_slice(),
+      [
+        "10.0.0.1:5432".parse::<SocketAddr>().unwrap(),
+        "10.0.0.2:5432".parse().unwrap()
+      ]
+    );
+    let addr = HostAddr::try_from("Cache.Internal:6379").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "[fd00::1]:6379".parse::<SocketAddr>().unwrap()
+    );
+
+    // IP addresses are not looked up
+    let addr = HostAddr::try_from("192.168.0.1:80").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "192.168.0.1:80".parse::<SocketAddr>().unwrap()
+    );
+
+    // and neither are domains holding an IP literal, e.g. decoded without validation
+    for (ip, expected) in [("10.1.2.3", "10.1.2.3:80"), ("fd00::9", "[fd00::9]:80")] {
+      let addr = HostAddr::from((Domain::from_ascii_unchecked(ip), 80));
+      let expected = expected.parse::<SocketAddr>().unwrap();
+      assert_eq!(resolver.resolve(&addr).await.unwrap(), expected);
+      assert_eq!(
+        resolver.resolve_all(&addr).await.unwrap().as_slice(),
+        [expected]
+      );
+    }
+
+    let addr = HostAddr::try_from("web.internal:80").unwrap();
+    assert!(matches!(
+      resolver.resolve(&addr).await,
+      Err(HostsFileError::NotFound(_))
+    ));
+    assert!(resolver.resolve_all(&addr).await.is_err());
+
+    assert!(matches!(
+      HostsFileResolver::<TokioRuntime>::new(
+        HostsFileResolverOptions::new().with_path(file.0.with_extension("missing")),
+      )
+      .await,
+      Err(HostsFileError::Io(_))
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_reload() {
+    let file = TempFile::new("hosts-reload", "10.0.0.1 db.internal\n");
+    let opts = HostsFileResolverOptions::new()
+      .with_path(&file.0)
+      .with_reload_interval(Some(Duration::from_millis(50)));
+    assert_eq!(opts.reload_interval(), Some(Duration::from_millis(50)));
+    let resolver = HostsFileResolver::<TokioRuntime>::new(opts).await.unwrap();
+
+    let addr = HostAddr::try_from("db.internal:80").unwrap();
+    std::fs::write(&file.0, "10.0.0.2 db.internal\n").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.1:80".parse::<SocketAddr>().unwrap()
+    );
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.2:80".parse::<SocketAddr>().unwrap()
+    );
+
+    // the previous entries are kept if the file cannot be read
+    std::fs::remove_file(&file.0).unwrap();
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(resolver.resolve(&addr).await.is_ok());
+    assert!(resolver.reload().await.is_err());
+  }
+}
@@ -0,0 +1,211 @@
+use std::future::Future;
+
+use futures::future::join;
+use smallvec::SmallVec;
+
+use super::super::AddressResolver;
+
+/// The error returned by [`MergeResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum MergeError<A, B> {
+  /// The first resolver failed.
+  #[error("first resolver: {0}")]
+  First(A),
+  /// The second resolver failed.
+  #[error("second resolver: {0}")]
+  Second(B),
+  /// Both resolvers failed.
+  #[error("first resolver: {0}; second resolver: {1}")]
+  Both(A, B),
+}
+
+/// A resolver which asks two resolvers for the same address concurrently, and merges
+/// their results.
+///
+/// [`resolve_all`](AddressResolver::resolve_all) returns the addresses of the first
+/// resolver followed by the ones of the second resolver, without duplicates, and
+/// only fails if both resolvers fail. [`resolve`](AddressResolver::resolve) prefers the
+/// address returned by the first resolver.
+///
+/// The [`Runtime`](AddressResolver::Runtime) of the merged resolver is the one of the
+/// first resolver.
+#[derive(Debug, Clone)]
+pub struct MergeResolver<A, B> {
+  first: A,
+  second: B,
+}
+
+impl<A, B> MergeResolver<A, B> {
+  /// Creates a new resolver which merges the results of `first` and `second`.
+  #[inline]
+  pub const fn new(first: A, second: B) -> Self {
+    Self { first, second }
+  }
+
+  /// Returns the first resolver.
+  #[inline]
+  pub const fn first(&self) -> &A {
+    &self.first
+  }
+
+  /// Returns the second resolver.
+  #[inline]
+  pub const fn second(&self) -> &B {
+    &self.second
+  }
+
+  /// Consumes the resolver and returns the two merged resolvers.
+  #[inline]
+  pub fn into_components(self) -> (A, B) {
+    (self.first, self.second)
+  }
+}
+
+impl<A, B> AddressResolver for MergeResolver<A, B>
+where
+  A: AddressResolver,
+  B: AddressResolver<Address = A::Address, ResolvedAddress = A::ResolvedAddress>,
+{
+  type Address = A::Address;
+  type ResolvedAddress = A::ResolvedAddress;
+  type Error = MergeError<A::Error, B::Error>;
+  type Runtime = A::Runtime;
+  type Options = (A::Options, B::Options);
+
+  async fn new((first, second): Self::Options) -> Result<Self, Self::Error>
+  where
+    Self: Sized,
+  {
+    match join(A::new(first), B::new(second)).await {
+      (Ok(first), Ok(second)) => Ok(Self { first, second }),
+      (Err(e), Ok(_)) => Err(MergeError::First(e)),
+      (Ok(_), Err(e)) => Err(MergeError::Second(e)),
+      (Err(a), Err(b)) => Err(MergeError::Both(a, b)),
+    }
+  }
+
+  fn resolve(
+    &self,
+    address: &Self::Address,
+  ) -> impl Future<Output = Result<Self::ResolvedAddress, Self::Error>> + Send {
+    // Create both lookups up front, so the returned future does not borrow `address`.
+    let lookups = join(self.first.resolve(address), self.second.resolve(address));
+    async move {
+      match lookups.await {
+        (Ok(addr), _) | (Err(_), Ok(addr)) => Ok(addr),
+        (Err(a), Err(b)) => Err(MergeError::Both(a, b)),
+      }
+    }
+  }
+
+  fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> impl Future<Output = Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error>> + Send {
+    let lookups = join(
+      self.first.resolve_all(address),
+      self.second.resolve_all(address),
+    );
+    async move {
+      match lookups.await {
+        (Ok(mut addrs), Ok(others)) => {
+          for addr in others {
+            if !addrs.contains(&addr) {
+              addrs.push(addr);
+            }
+          }
+          Ok(addrs)
+        }
+        (Ok(addrs), Err(_)) | (Err(_), Ok(addrs)) => Ok(addrs),
+        (Err(a), Err(b)) => Err(MergeError::Both(a, b)),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{io, net::SocketAddr};
+
+  use agnostic::tokio::TokioRuntime;
+
+  use super::*;
+  use crate::HostAddr;
+
+  /// Resolves every address to a fixed set of addresses, or fails if the set is empty.
+  struct FixedResolver(Vec<SocketAddr>);
+
+  impl AddressResolver for FixedResolver {
+    type Address = HostAddr;
+    type ResolvedAddress = SocketAddr;
+    type Error = io::Error;
+    type Runtime = TokioRuntime;
+    type Options = Vec<SocketAddr>;
+
+    async fn new(addrs: Self::Options) -> Result<Self, Self::Error> {
+      Ok(Self(addrs))
+    }
+
+    async fn resolve(&self, _: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
+      self
+        .0
+        .first()
+        .copied()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses"))
+    }
+
+    async fn resolve_all(
+      &self,
+      address: &Self::Address,
+    ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+      self.resolve(address).await?;
+      Ok(self.0.iter().copied().collect())
+    }
+  }
+
+  fn addrs(addrs: &[&str]) -> Vec<SocketAddr> {
+    addrs.iter().map(|addr| addr.parse().unwrap()).collect()
+  }
+
+  #[tokio::test]
+  async fn test_merge_resolver() {
+    let addr = HostAddr::try_from("example.com:80").unwrap();
+    let resolver = <MergeResolver<FixedResolver, FixedResolver> as AddressResolver>::new((
+      addrs(&["10.0.0.1:80", "10.0.0.2:80"]),
+      addrs(&["10.0.0.3:80", "10.0.0.1:80", "[::1]:80"]),
+    ))
+    .await
+    .unwrap();
+
+    let merged = resolver.resolve_all(&addr).await.unwrap();
+    assert_eq!(
+      merged.as_slice(),
+      addrs(&["10.0.0.1:80", "10.0.0.2:80", "10.0.0.3:80", "[::1]:80"])
+    );
+    assert_eq!(resolver.resolve(&addr).await.unwrap(), merged[0]);
+
+    // one of the resolvers failing is not an error
+    let resolver = MergeResolver::new(
+      FixedResolver(vec![]),
+      FixedResolver(addrs(&["10.0.0.3:80"])),
+    );
+    assert_eq!(
+      resolver.resolve_all(&addr).await.unwrap().as_slice(),
+      addrs(&["10.0.0.3:80"])
+    );
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.3:80".parse::<SocketAddr>().unwrap()
+    );
+
+    let resolver = MergeResolver::new(FixedResolver(vec![]), FixedResolver(vec![]));
+    assert!(matches!(
+      resolver.resolve_all(&addr).await.unwrap_err(),
+      MergeError::Both(_, _)
+    ));
+    assert!(matches!(
+      resolver.resolve(&addr).await.unwrap_err(),
+      MergeError::Both(_, _)
+    ));
+  }
+}
@@ -0,0 +1,177 @@
+use std::{
+  collections::HashMap,
+  net::{IpAddr, SocketAddr},
+};
+
+use agnostic::RuntimeLite;
+use smallvec::SmallVec;
+
+use super::super::AddressResolver;
+use crate::{address::Domain, HostAddr};
+
+/// The error returned by [`StaticResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum StaticResolveError {
+  /// The domain is not in the host map, or has no addresses.
+  #[error("no address found for {0}")]
+  NotFound(Domain),
+}
+
+/// A resolver which resolves domains from an in-memory host map, and never sends DNS queries.
+///
+/// IP addresses are returned directly, and domains are looked up in the host map given
+/// as [`Options`](AddressResolver::Options), combined with the port of the address.
+/// This is useful for tests and air-gapped deployments.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use nodecraft::{resolver::{r#static::StaticResolver, AddressResolver}, Domain, HostAddr};
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let hosts = HashMap::from([(
+///   Domain::try_from("db.internal").unwrap(),
+///   vec!["10.0.0.1".parse().unwrap()],
+/// )]);
+/// let resolver = StaticResolver::<agnostic::tokio::TokioRuntime>::new(hosts).await.unwrap();
+/// let addr = HostAddr::try_from("db.internal:5432").unwrap();
+/// assert_eq!(resolver.resolve(&addr).await.unwrap().to_string(), "10.0.0.1:5432");
+/// # });
+/// ```
+pub struct StaticResolver<R> {
+  hosts: HashMap<Domain, Vec<IpAddr>>,
+  _marker: std::marker::PhantomData<R>,
+}
+
+impl<R> StaticResolver<R> {
+  /// Returns the host map of the resolver.
+  #[inline]
+  pub const fn hosts(&self) -> &HashMap<Domain, Vec<IpAddr>> {
+    &self.hosts
+  }
+
+  fn lookup(&self, name: &Domain) -> Result<&[IpAddr], StaticResolveError> {
+    match self.hosts.get(name) {
+      Some(ips) if !ips.is_empty() => Ok(ips),
+      _ => Err(StaticResolveError::NotFound(name.clone())),
+    }
+  }
+}
+
+impl<R> From<HashMap<Domain, Vec<IpAddr>>> for StaticResolver<R> {
+  #[inline]
+  fn from(hosts: HashMap<Domain, Vec<IpAddr>>) -> Self {
+    Self {
+      hosts,
+      _marker: std::marker::PhantomData,
+    }
+  }
+}
+
+impl<R: RuntimeLite> AddressResolver for StaticResolver<R> {
+  type Address = HostAddr;
+  type ResolvedAddress = SocketAddr;
+  type Error = StaticResolveError;
+  type Runtime = R;
+  type Options = HashMap<Domain, Vec<IpAddr>>;
+
+  #[inline]
+  async fn new(hosts: Self::Options) -> Result<Self, Self::Error> {
+    Ok(Self::from(hosts))
+  }
+
+  async fn resolve(&self, address: &Self::Address) -> Result<Self::ResolvedAddress, Self::Error> {
+    match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => Ok(addr),
+      Err(name) => self
+        .lookup(name)
+        .map(|ips| SocketAddr::new(ips[0], address.port)),
+    }
+  }
+
+  async fn resolve_all(
+    &self,
+    address: &Self::Address,
+  ) -> Result<SmallVec<[Self::ResolvedAddress; 4]>, Self::Error> {
+    match address.kind.literal_socket_addr(address.port) {
+      Ok(addr) => Ok(smallvec::smallvec![addr]),
+      Err(name) => self.lookup(name).map(|ips| {
+        ips
+          .iter()
+          .map(|ip| SocketAddr::new(*ip, address.port))
+          .collect()
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use agnostic::tokio::TokioRuntime;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_static_resolver() {
+    let hosts = HashMap::from([
+      (
+        Domain::try_from("db.internal").unwrap(),
+        vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()],
+      ),
+      (
+        Domain::try_from("cache.internal").unwrap(),
+        vec!["::1".parse().unwrap()],
+      ),
+      (Domain::try_from("empty.internal").unwrap(), vec![]),
+    ]);
+    let resolver = StaticResolver::<TokioRuntime>::new(hosts).await.unwrap();
+
+    let addr = HostAddr::try_from("db.internal:5432").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "10.0.0.1:5432".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(
+      resolver.resolve_all(&addr).await.unwrap().as_slice(),
+      [
+        "10.0.0.1:5432".parse::<SocketAddr>().unwrap(),
+        "10.0.0.2:5432".parse::<SocketAddr>().unwrap()
+      ]
+    );
+
+    let addr = HostAddr::try_from("cache.internal.:6379").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "[::1]:6379".parse::<SocketAddr>().unwrap()
+    );
+
+    // IP addresses are not looked up
+    let addr = HostAddr::try_from("192.168.0.1:80").unwrap();
+    assert_eq!(
+      resolver.resolve(&addr).await.unwrap(),
+      "192.168.0.1:80".parse::<SocketAddr>().unwrap()
+    );
+
+    // and neither are domains holding an IP literal, e.g. decoded without validation
+    for (ip, expected) in [("10.1.2.3", "10.1.2.3:80"), ("fd00::9", "[fd00::9]:80")] {
+      let addr = HostAddr::from((Domain::from_ascii_unchecked(ip), 80));
+      let expected = expected.parse::<SocketAddr>().unwrap();
+      assert_eq!(resolver.resolve(&addr).await.unwrap(), expected);
+      assert_eq!(
+        resolver.resolve_all(&addr).await.unwrap().as_slice(),
+        [expected]
+      );
+    }
+
+    for miss in ["web.internal:80", "empty.internal:80"] {
+      let addr = HostAddr::try_from(miss).unwrap();
+      assert!(matches!(
+        resolver.resolve(&addr).await,
+        Err(StaticResolveError::NotFound(_))
+      ));
+      assert!(resolver.resolve_all(&addr).await.is_err());
+    }
+  }
+}
@@ -0,0 +1,25 @@
+//! `HostAddr` can be parsed, constructed and displayed without `std`, e.g.
+//! `cargo test --no-default-features --features alloc --test no_std`.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::ToString;
+use core::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+use nodecraft::{Domain, HostAddr};
+
+#[test]
+fn host_addr() {
+  let addr: HostAddr = "[::1]:8080".parse().unwrap();
+  assert_eq!(addr.ip(), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+  assert_eq!(addr.port(), 8080);
+  assert_eq!(addr.to_string(), "[::1]:8080");
+
+  let addr = HostAddr::from(SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 8080));
+  assert_eq!(addr.to_string(), "[::1]:8080");
+
+  let addr = HostAddr::from((Domain::try_from("www.example.com").unwrap(), 80));
+  assert_eq!(addr.to_string(), "www.example.com:80");
+  assert_eq!("www.example.com:80".parse::<HostAddr>().unwrap(), addr);
+}